@@ -0,0 +1,258 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use teloxide::{
+    prelude::*,
+    types::{InlineKeyboardButton, InlineKeyboardMarkup},
+};
+use tokio::sync::broadcast;
+
+use crate::{
+    constants::{ADMIN_CHAT_IDS, USER_REGISTRATIONS},
+    data_backend::mensa_name,
+    data_types::{HandlerResult, JobHandlerTask, UpdateScheduleTask, UserSettings, WeekSchedule},
+    db_operations::{get_user_watches, save_user_schedule, save_user_settings},
+    shared_main::{
+        get_user_registration, insert_user_registration, settings_text, week_schedule_text,
+    },
+    task_scheduler_funcs::handle_send_error_task,
+};
+
+// stays well below Telegram's limit of 30 messages per second
+const BROADCAST_DELAY: Duration = Duration::from_millis(50);
+const HISTOGRAM_WIDTH: usize = 20;
+
+/// Chats in `ADMIN_CHAT_IDS`, the texts of their commands are not localised
+pub fn is_operator(chat_id: ChatId) -> bool {
+    ADMIN_CHAT_IDS
+        .get()
+        .is_some_and(|ids| ids.contains(&chat_id.0))
+}
+
+pub async fn stats_cmd(bot: Bot, msg: Message) -> HandlerResult {
+    let registrations = USER_REGISTRATIONS.get().unwrap().read().unwrap().clone();
+
+    // mensa id -> (main mensa, additionally followed)
+    let mut per_mensa: BTreeMap<u32, (usize, usize)> = BTreeMap::new();
+    let mut send_times: BTreeMap<(u32, u32), usize> = BTreeMap::new();
+    let mut subscribed = 0;
+    for registration in registrations.values() {
+        per_mensa.entry(registration.mensa_id).or_default().0 += 1;
+        for mensa_id in &registration.additional_mensa_ids {
+            per_mensa.entry(*mensa_id).or_default().1 += 1;
+        }
+
+        if let (Some(hour), Some(minute)) = (registration.hour, registration.minute) {
+            subscribed += 1;
+            for time in registration
+                .schedule
+                .delivery_times(hour, minute)
+                .into_keys()
+            {
+                *send_times.entry(time).or_default() += 1;
+            }
+        }
+    }
+
+    let mut mensen: Vec<(u32, (usize, usize))> = per_mensa.into_iter().collect();
+    mensen.sort_by_key(|(_, counts)| std::cmp::Reverse(*counts));
+
+    let mut text = format!(
+        "Registrierungen: {}\nAutom. Nachrichten: {}\n\nMensen (Haupt / zusätzlich):",
+        registrations.len(),
+        subscribed
+    );
+    for (mensa_id, (main, additional)) in mensen {
+        text += &format!("\n{}: {} / {}", mensa_name(mensa_id), main, additional);
+    }
+
+    text += "\n\nSendezeiten:";
+    let max_count = send_times.values().copied().max().unwrap_or(1);
+    for ((hour, minute), count) in send_times {
+        let bar_len = (count * HISTOGRAM_WIDTH).div_ceil(max_count);
+        text += &format!(
+            "\n{:02}:{:02} {} {}",
+            hour,
+            minute,
+            "█".repeat(bar_len),
+            count
+        );
+    }
+
+    bot.send_message(msg.chat.id, text).await?;
+
+    Ok(())
+}
+
+/// Shows the announcement as preview, it is only sent after confirming
+pub async fn broadcast_cmd(bot: Bot, msg: Message, text: String) -> HandlerResult {
+    let text = text.trim();
+    if text.is_empty() {
+        bot.send_message(msg.chat.id, "Beispiel:\n/broadcast Heute keine Updates.")
+            .await?;
+        return Ok(());
+    }
+
+    let chat_count = USER_REGISTRATIONS.get().unwrap().read().unwrap().len();
+    let keyboard = InlineKeyboardMarkup::new([
+        [InlineKeyboardButton::callback(
+            format!("📢 An {} Chats senden", chat_count),
+            "adm_bc:send",
+        )],
+        [InlineKeyboardButton::callback("Abbrechen", "adm_bc:cancel")],
+    ]);
+    bot.send_message(msg.chat.id, text)
+        .reply_markup(keyboard)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn user_cmd(bot: Bot, msg: Message, chat_id: String) -> HandlerResult {
+    let Ok(chat_id) = chat_id.trim().parse::<i64>() else {
+        bot.send_message(msg.chat.id, "Beispiel:\n/user 123456789")
+            .await?;
+        return Ok(());
+    };
+
+    match get_user_registration(chat_id) {
+        Some(registration) => {
+            let watches = get_user_watches(chat_id).await?;
+            let text = format!(
+                "Chat {}\n\n{}\n\n{}\n\nGemerkt: {}",
+                chat_id,
+                settings_text(&registration),
+                week_schedule_text(&registration),
+                match watches.is_empty() {
+                    true => "-".to_string(),
+                    false => watches.join(", "),
+                }
+            );
+            let keyboard = InlineKeyboardMarkup::new([[InlineKeyboardButton::callback(
+                "Einstellungen zurücksetzen",
+                format!("adm_reset:{}", chat_id),
+            )]]);
+            bot.send_message(msg.chat.id, text)
+                .reply_markup(keyboard)
+                .await?;
+        }
+        None => {
+            bot.send_message(
+                msg.chat.id,
+                format!("Chat {} ist nicht registriert.", chat_id),
+            )
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Callbacks of the keyboards above, their data starts with "adm_"
+pub async fn admin_callback_handler(
+    bot: Bot,
+    q: CallbackQuery,
+    jobhandler_task_tx: broadcast::Sender<JobHandlerTask>,
+) -> HandlerResult {
+    let (Some(q_data), Some(message)) = (q.data, q.message) else {
+        return Ok(());
+    };
+    bot.answer_callback_query(q.id).await?;
+
+    let chat_id = message.chat().id;
+    // the keyboards could have been forwarded
+    if !is_operator(chat_id) {
+        return Ok(());
+    }
+
+    let Some((cmd, arg)) = q_data.split_once(':') else {
+        log::warn!("Malformed admin callback: {}", q_data);
+        return Ok(());
+    };
+    match (cmd, arg) {
+        ("adm_bc", "send") => {
+            let Some(text) = message
+                .regular_message()
+                .and_then(|msg| msg.text())
+                .map(str::to_string)
+            else {
+                return Ok(());
+            };
+            // no second broadcast by pressing again
+            bot.edit_message_reply_markup(chat_id, message.id()).await?;
+
+            tokio::spawn(async move {
+                let summary = match send_broadcast(&bot, &text, &jobhandler_task_tx).await {
+                    Ok((sent, failed)) => format!("Gesendet: {}\nFehlgeschlagen: {}", sent, failed),
+                    Err(e) => {
+                        log::error!("Broadcast aborted: {}", e);
+                        format!("Broadcast abgebrochen: {}", e)
+                    }
+                };
+                if let Err(e) = bot.send_message(chat_id, summary).await {
+                    log::error!("Sending broadcast summary failed: {}", e);
+                }
+            });
+        }
+        ("adm_bc", "cancel") => {
+            bot.edit_message_text(chat_id, message.id(), "Broadcast abgebrochen.")
+                .await?;
+        }
+        ("adm_reset", target) => {
+            let Ok(target) = target.parse::<i64>() else {
+                log::warn!("Malformed admin callback: {}", q_data);
+                return Ok(());
+            };
+            let text = match get_user_registration(target) {
+                Some(mut registration) => {
+                    registration.settings = UserSettings {
+                        lang: registration.settings.lang,
+                        ..Default::default()
+                    };
+                    registration.schedule = WeekSchedule::default();
+                    save_user_settings(target, &registration.settings).await?;
+                    save_user_schedule(target, &registration.schedule).await?;
+                    insert_user_registration(target, registration);
+                    // the send jobs follow the weekdays
+                    jobhandler_task_tx.send(UpdateScheduleTask { chat_id: target }.into())?;
+                    format!("Einstellungen von Chat {} zurückgesetzt.", target)
+                }
+                None => format!("Chat {} ist nicht registriert.", target),
+            };
+            bot.send_message(chat_id, text).await?;
+        }
+        _ => log::warn!("Unknown admin callback: {}", q_data),
+    }
+
+    Ok(())
+}
+
+/// Sends to every registered chat, returns (sent, failed)
+async fn send_broadcast(
+    bot: &Bot,
+    text: &str,
+    jobhandler_task_tx: &broadcast::Sender<JobHandlerTask>,
+) -> Result<(usize, usize), Box<dyn std::error::Error + Send + Sync>> {
+    let chat_ids: Vec<i64> = USER_REGISTRATIONS
+        .get()
+        .unwrap()
+        .read()
+        .unwrap()
+        .keys()
+        .copied()
+        .collect();
+    log::info!("Broadcasting to {} chats", chat_ids.len());
+
+    let (mut sent, mut failed) = (0, 0);
+    for chat_id in chat_ids {
+        match bot.send_message(ChatId(chat_id), text).await {
+            Ok(_) => sent += 1,
+            Err(e) => {
+                handle_send_error_task(chat_id, e, jobhandler_task_tx)?;
+                failed += 1;
+            }
+        }
+        tokio::time::sleep(BROADCAST_DELAY).await;
+    }
+
+    Ok((sent, failed))
+}
@@ -0,0 +1,183 @@
+use std::collections::BTreeSet;
+
+/// EU allergen codes as used on German canteen plans (A1 = wheat is part of A),
+/// their names are in `i18n::Texts::allergen_names`
+pub const ALLERGENS: [char; 14] = [
+    'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L', 'M', 'N',
+];
+
+/// Allergens and additives of a meal
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AllergenCodes {
+    /// bit n = n-th entry of `ALLERGENS`
+    pub allergens: u16,
+    /// additive numbers, e.g. 2 = Konservierungsstoff
+    pub additives: BTreeSet<u8>,
+}
+
+impl AllergenCodes {
+    /// Names of the allergens that are also in `mask`
+    pub fn matching_names(
+        &self,
+        mask: u16,
+        names: &[&'static str; ALLERGENS.len()],
+    ) -> Vec<&'static str> {
+        names
+            .iter()
+            .enumerate()
+            .filter(|(bit, _)| self.allergens & mask & (1 << bit) != 0)
+            .map(|(_, name)| *name)
+            .collect()
+    }
+}
+
+/// Bit of an allergen code letter in `AllergenCodes::allergens`
+pub fn allergen_bit(code: char) -> Option<u16> {
+    ALLERGENS
+        .iter()
+        .position(|letter| *letter == code)
+        .map(|bit| 1 << bit)
+}
+
+/// Parses raw allergen strings, both code lists ("A1, C, G, 3") and
+/// written out names ("Weizen, Milch und Milcherzeugnisse").
+pub fn parse_allergens(raw: &str) -> AllergenCodes {
+    let mut codes = AllergenCodes::default();
+
+    for token in raw
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|token| !token.is_empty())
+    {
+        let mut chars = token.chars();
+        let first = chars.next().unwrap();
+        let rest = chars.as_str();
+
+        if let (Some(bit), true) = (
+            allergen_bit(first),
+            rest.len() <= 2 && rest.chars().all(|c| c.is_ascii_digit()),
+        ) {
+            // "A", "A1", "H12"
+            codes.allergens |= bit;
+        } else if let Ok(additive) = token.parse::<u8>() {
+            codes.additives.insert(additive);
+        } else if let Some(bit) = allergen_name_bit(&token.to_lowercase()) {
+            codes.allergens |= bit;
+        }
+    }
+
+    codes
+}
+
+fn allergen_name_bit(word: &str) -> Option<u16> {
+    let code = match word {
+        w if ["gluten", "weizen", "roggen", "gerste", "hafer", "dinkel"]
+            .iter()
+            .any(|name| w.starts_with(name)) =>
+        {
+            'A'
+        }
+        w if w.starts_with("krebs") => 'B',
+        w if w.starts_with("eier") || w == "ei" => 'C',
+        w if w.starts_with("fisch") => 'D',
+        // before nuts, "erdnüsse" contains "nüsse"
+        w if w.starts_with("erdnu") || w.starts_with("erdnü") => 'E',
+        w if w.starts_with("soja") => 'F',
+        w if w.starts_with("milch") || w.starts_with("laktose") || w.starts_with("lactose") => 'G',
+        w if w.starts_with("schalenfr")
+            || w.contains("nuss")
+            || w.contains("nüss")
+            || w.starts_with("mandel") =>
+        {
+            'H'
+        }
+        w if w.starts_with("sellerie") => 'I',
+        w if w.starts_with("senf") => 'J',
+        w if w.starts_with("sesam") => 'K',
+        w if w.starts_with("sulfit") || w.starts_with("schwefeldioxid") => 'L',
+        w if w.starts_with("lupine") => 'M',
+        w if w.starts_with("weichtier") => 'N',
+        _ => return None,
+    };
+
+    allergen_bit(code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mask(codes: &str) -> u16 {
+        codes
+            .chars()
+            .filter_map(allergen_bit)
+            .fold(0, |mask, bit| mask | bit)
+    }
+
+    #[test]
+    fn codes_with_suffixes() {
+        assert_eq!(parse_allergens("A1").allergens, mask("A"));
+        assert_eq!(parse_allergens("H12").allergens, mask("H"));
+        assert_eq!(parse_allergens("A, C, G").allergens, mask("ACG"));
+        // no allergen code
+        assert_eq!(parse_allergens("A123").allergens, 0);
+    }
+
+    #[test]
+    fn additives_are_separate() {
+        let codes = parse_allergens("A1, C, G, 3, 12");
+        assert_eq!(codes.allergens, mask("ACG"));
+        assert_eq!(codes.additives, BTreeSet::from([3, 12]));
+        assert_eq!(parse_allergens("2").allergens, 0);
+    }
+
+    #[test]
+    fn written_out_names() {
+        // not E (peanuts) through the code letter
+        assert_eq!(parse_allergens("Ei").allergens, mask("C"));
+        assert_eq!(parse_allergens("Eier").allergens, mask("C"));
+        // not H (tree nuts)
+        assert_eq!(parse_allergens("Erdnüsse").allergens, mask("E"));
+        assert_eq!(parse_allergens("Haselnüsse").allergens, mask("H"));
+        assert_eq!(
+            parse_allergens("Milch und Milcherzeugnisse").allergens,
+            mask("G")
+        );
+        assert_eq!(parse_allergens("Gluten, Senf").allergens, mask("AJ"));
+        assert_eq!(parse_allergens("Zucker").allergens, 0);
+    }
+
+    #[test]
+    fn html_fixture_strings() {
+        assert_eq!(
+            parse_allergens("Weizen (A1), Milch (G), Sellerie (I)").allergens,
+            mask("AGI")
+        );
+        assert_eq!(parse_allergens("A1, G, H3").allergens, mask("AGH"));
+    }
+
+    #[test]
+    fn names_of_matching_allergens() {
+        let names = [
+            "Gluten",
+            "Krebstiere",
+            "Eier",
+            "Fisch",
+            "Erdnüsse",
+            "Soja",
+            "Milch",
+            "Nüsse",
+            "Sellerie",
+            "Senf",
+            "Sesam",
+            "Sulfite",
+            "Lupinen",
+            "Weichtiere",
+        ];
+        let codes = parse_allergens("A1, G, H3");
+        assert_eq!(
+            codes.matching_names(mask("GHN"), &names),
+            ["Milch", "Nüsse"]
+        );
+        assert!(codes.matching_names(0, &names).is_empty());
+    }
+}
@@ -1,38 +1,58 @@
 use crate::bot_command_helpers::{
-    mensa_disp_or_upd, parse_time_send_status_msgs, send_bloat_image,
+    mensa_disp_or_upd, parse_time_send_status_msgs, send_bloat_image, set_weekday_time,
 };
-use crate::constants::NO_DB_MSG;
+use crate::constants::{BACKEND, WATCH_KEYWORD_MAX_LEN};
+use crate::data_backend::resolve_days_forward;
 use crate::data_types::{
     Command, DialogueState, DialogueType, HandlerResult, JobHandlerTask, MensaKeyboardAction,
     UnregisterTask, UpdateRegistrationTask,
 };
 
-use crate::db_operations::set_user_allergen_state;
+use crate::db_operations::{add_watch, get_user_watches, remove_watch, save_user_settings};
+use crate::german_date_parser::{parse_german_date, parse_german_weekday};
+use crate::i18n::Lang;
 use crate::shared_main::{
-    build_meal_message_dispatcher, get_user_registration, insert_user_registration,
-    make_commands_keyrow, make_mensa_keyboard,
+    build_meal_message_dispatcher, first_week_day, get_user_registration, insert_user_registration,
+    make_allergen_keyboard, make_commands_keyrow, make_diet_keyboard, make_follow_keyboard,
+    make_lang_keyboard, make_mensa_keyboard, make_price_tier_keyboard, make_unwatch_keyboard,
+    make_week_keyboard, make_weekday_keyboard, settings_page, week_schedule_text,
 };
 use rand::Rng;
 use std::{collections::BTreeMap, time::Instant};
 use teloxide::{prelude::*, types::ParseMode};
 use tokio::sync::broadcast;
 
-pub async fn start(bot: Bot, msg: Message, mensen: BTreeMap<u32, String>) -> HandlerResult {
+pub async fn start(
+    bot: Bot,
+    msg: Message,
+    mensen: BTreeMap<u32, String>,
+    lang: Lang,
+) -> HandlerResult {
     let keyboard = make_mensa_keyboard(mensen, MensaKeyboardAction::Register);
-    bot.send_message(msg.chat.id, "Mensa auswählen:")
+    bot.send_message(msg.chat.id, lang.texts().choose_mensa)
         .reply_markup(keyboard)
         .await?;
 
     Ok(())
 }
 
-pub async fn invalid_cmd(bot: Bot, msg: Message) -> HandlerResult {
-    bot.send_message(msg.chat.id, "Das ist kein Befehl.")
+pub async fn invalid_cmd(bot: Bot, msg: Message, lang: Lang) -> HandlerResult {
+    // groups are full of other messages and commands for other bots
+    if msg.chat.is_private() {
+        bot.send_message(msg.chat.id, lang.texts().not_a_command)
+            .await?;
+    }
+    Ok(())
+}
+
+pub async fn admin_only_cmd(bot: Bot, msg: Message, lang: Lang) -> HandlerResult {
+    bot.send_message(msg.chat.id, lang.texts().admin_only)
         .await?;
     Ok(())
 }
 
-pub async fn day_cmd(bot: Bot, msg: Message, cmd: Command) -> HandlerResult {
+pub async fn day_cmd(bot: Bot, msg: Message, cmd: Command, lang: Lang) -> HandlerResult {
+    let texts = lang.texts();
     let days_forward = match cmd {
         Command::Heute => 0,
         Command::Morgen => 1,
@@ -41,13 +61,18 @@ pub async fn day_cmd(bot: Bot, msg: Message, cmd: Command) -> HandlerResult {
     };
 
     if let Some(registration) = get_user_registration(msg.chat.id.0) {
-        let text =
-            build_meal_message_dispatcher(msg.chat.id.0, days_forward, registration.mensa_id).await;
+        let text = build_meal_message_dispatcher(
+            msg.chat.id.0,
+            resolve_days_forward(days_forward),
+            &registration.mensa_ids(),
+            lang,
+        )
+        .await;
         let now = Instant::now();
 
         bot.send_message(msg.chat.id, text)
             .parse_mode(ParseMode::MarkdownV2)
-            .reply_markup(make_commands_keyrow())
+            .reply_markup(make_commands_keyrow(lang))
             .await?;
 
         log::debug!("Send {:?} msg: {:.2?}", cmd, now.elapsed());
@@ -56,8 +81,51 @@ pub async fn day_cmd(bot: Bot, msg: Message, cmd: Command) -> HandlerResult {
         // if this is none, it most likely means the DB was wiped)
         // forcing reregistration is better than crashing the bot, no data will be overwritten anyways
         // but copy pasting this everywhere is ugly
-        bot.send_message(msg.chat.id, NO_DB_MSG).await?;
+        bot.send_message(msg.chat.id, texts.no_db).await?;
+    }
+    Ok(())
+}
+
+pub async fn week_cmd(bot: Bot, msg: Message, lang: Lang) -> HandlerResult {
+    let texts = lang.texts();
+    if let Some(registration) = get_user_registration(msg.chat.id.0) {
+        let date = first_week_day(chrono::Local::now().naive_local());
+        let text =
+            build_meal_message_dispatcher(msg.chat.id.0, date, &registration.mensa_ids(), lang)
+                .await;
+
+        bot.send_message(msg.chat.id, text)
+            .parse_mode(ParseMode::MarkdownV2)
+            .reply_markup(make_week_keyboard(date, lang))
+            .await?;
+    } else {
+        bot.send_message(msg.chat.id, texts.no_db).await?;
+    }
+    Ok(())
+}
+
+pub async fn tag_cmd(bot: Bot, msg: Message, date: String, lang: Lang) -> HandlerResult {
+    let texts = lang.texts();
+    let Some(registration) = get_user_registration(msg.chat.id.0) else {
+        bot.send_message(msg.chat.id, texts.no_db).await?;
+        return Ok(());
+    };
+
+    match parse_german_date(&date, chrono::Local::now().date_naive()) {
+        Some(date) => {
+            let text =
+                build_meal_message_dispatcher(msg.chat.id.0, date, &registration.mensa_ids(), lang)
+                    .await;
+            bot.send_message(msg.chat.id, text)
+                .parse_mode(ParseMode::MarkdownV2)
+                .await?;
+        }
+        None => {
+            bot.send_message(msg.chat.id, texts.date_not_recognized)
+                .await?;
+        }
     }
+
     Ok(())
 }
 
@@ -65,32 +133,29 @@ pub async fn show_different_mensa(
     bot: Bot,
     msg: Message,
     mensen: BTreeMap<u32, String>,
+    lang: Lang,
 ) -> HandlerResult {
-    mensa_disp_or_upd(bot, msg, mensen, MensaKeyboardAction::DisplayOnce).await
+    mensa_disp_or_upd(bot, msg, mensen, MensaKeyboardAction::DisplayOnce, lang).await
 }
 
 pub async fn subscribe(
     bot: Bot,
     msg: Message,
     jobhandler_task_tx: broadcast::Sender<JobHandlerTask>,
+    lang: Lang,
 ) -> HandlerResult {
+    let texts = lang.texts();
     if let Some(registration) = get_user_registration(msg.chat.id.0) {
-        if registration.job_uuid.is_some() {
+        if registration.hour.is_some() {
             if rand::thread_rng().gen_range(0..10) == 0 {
                 send_bloat_image(&bot, msg.chat.id).await;
             }
 
-            bot.send_message(
-                msg.chat.id,
-                "Automatische Nachrichten sind schon aktiviert.",
-            )
-            .await?;
+            bot.send_message(msg.chat.id, texts.auto_already_on).await?;
         } else {
-            bot.send_message(
-                        msg.chat.id,
-                        "Plan wird ab jetzt automatisch an Wochentagen *06:00 Uhr* gesendet.\n\nÄndern mit /uhrzeit",
-                    ).parse_mode(ParseMode::MarkdownV2)
-                    .await?;
+            bot.send_message(msg.chat.id, texts.auto_now_on)
+                .parse_mode(ParseMode::MarkdownV2)
+                .await?;
 
             let registration_job = UpdateRegistrationTask {
                 chat_id: msg.chat.id.0,
@@ -103,7 +168,7 @@ pub async fn subscribe(
             jobhandler_task_tx.send(registration_job).unwrap();
         }
     } else {
-        bot.send_message(msg.chat.id, NO_DB_MSG).await?;
+        bot.send_message(msg.chat.id, texts.no_db).await?;
     }
 
     Ok(())
@@ -113,17 +178,15 @@ pub async fn unsubscribe(
     bot: Bot,
     msg: Message,
     jobhandler_task_tx: broadcast::Sender<JobHandlerTask>,
+    lang: Lang,
 ) -> HandlerResult {
+    let texts = lang.texts();
     if let Some(registration) = get_user_registration(msg.chat.id.0) {
-        if registration.job_uuid.is_none() {
-            bot.send_message(
-                msg.chat.id,
-                "Automatische Nachrichten waren bereits deaktiviert.",
-            )
-            .await?;
-        } else {
-            bot.send_message(msg.chat.id, "Plan wird nicht mehr automatisch gesendet.")
+        if registration.hour.is_none() {
+            bot.send_message(msg.chat.id, texts.auto_already_off)
                 .await?;
+        } else {
+            bot.send_message(msg.chat.id, texts.auto_now_off).await?;
 
             jobhandler_task_tx
                 .send(
@@ -135,64 +198,245 @@ pub async fn unsubscribe(
                 .unwrap();
         }
     } else {
-        bot.send_message(msg.chat.id, NO_DB_MSG).await?;
+        bot.send_message(msg.chat.id, texts.no_db).await?;
     }
     Ok(())
 }
 
-pub async fn change_mensa(bot: Bot, msg: Message, mensen: BTreeMap<u32, String>) -> HandlerResult {
-    mensa_disp_or_upd(bot, msg, mensen, MensaKeyboardAction::Update).await
+pub async fn change_mensa(
+    bot: Bot,
+    msg: Message,
+    mensen: BTreeMap<u32, String>,
+    lang: Lang,
+) -> HandlerResult {
+    mensa_disp_or_upd(bot, msg, mensen, MensaKeyboardAction::Update, lang).await
+}
+
+pub async fn follow_mensen(
+    bot: Bot,
+    msg: Message,
+    mensen: BTreeMap<u32, String>,
+    lang: Lang,
+) -> HandlerResult {
+    let texts = lang.texts();
+    if let Some(registration) = get_user_registration(msg.chat.id.0) {
+        bot.send_message(msg.chat.id, texts.choose_follow)
+            .reply_markup(make_follow_keyboard(&mensen, &registration))
+            .await?;
+    } else {
+        bot.send_message(msg.chat.id, texts.no_db).await?;
+    }
+
+    Ok(())
 }
 
-pub async fn allergene(bot: Bot, msg: Message) -> HandlerResult {
+pub async fn allergene(bot: Bot, msg: Message, lang: Lang) -> HandlerResult {
+    let texts = lang.texts();
     if let Some(mut registration) = get_user_registration(msg.chat.id.0) {
-        registration.allergens = !registration.allergens;
+        registration.settings.allergens = !registration.settings.allergens;
 
-        set_user_allergen_state(msg.chat.id.0, registration.allergens)?;
-        insert_user_registration(msg.chat.id.0, registration);
+        save_user_settings(msg.chat.id.0, &registration.settings).await?;
+        insert_user_registration(msg.chat.id.0, registration.clone());
 
-        match registration.allergens {
+        match registration.settings.allergens {
             true => {
-                bot.send_message(msg.chat.id, "✅ Allergene werden jetzt angezeigt.")
-                    .await?;
+                bot.send_message(msg.chat.id, texts.allergens_on).await?;
             }
             false => {
-                bot.send_message(msg.chat.id, "❌ Allergene werden nicht mehr angezeigt.")
-                    .await?;
+                bot.send_message(msg.chat.id, texts.allergens_off).await?;
             }
         }
     } else {
-        bot.send_message(msg.chat.id, NO_DB_MSG).await?;
+        bot.send_message(msg.chat.id, texts.no_db).await?;
     }
 
     Ok(())
 }
 
-pub async fn senddiff(bot: Bot, msg: Message) -> HandlerResult {
+pub async fn senddiff(bot: Bot, msg: Message, lang: Lang) -> HandlerResult {
+    let texts = lang.texts();
     if let Some(mut registration) = get_user_registration(msg.chat.id.0) {
-        registration.senddiff = !registration.senddiff;
+        registration.settings.senddiff = !registration.settings.senddiff;
 
-        set_user_allergen_state(msg.chat.id.0, registration.senddiff)?;
-        insert_user_registration(msg.chat.id.0, registration);
+        save_user_settings(msg.chat.id.0, &registration.settings).await?;
+        insert_user_registration(msg.chat.id.0, registration.clone());
 
-        match registration.senddiff {
+        match registration.settings.senddiff {
             true => {
-                bot.send_message(
-                    msg.chat.id,
-                    "✅ Nur Änderungen werden bei Planänderung gesendet.",
-                )
-                .await?;
+                bot.send_message(msg.chat.id, texts.diff_on).await?;
             }
             false => {
-                bot.send_message(
-                    msg.chat.id,
-                    "❌ Gesamter Plan wird bei Änderungen gesendet.",
-                )
-                .await?;
+                bot.send_message(msg.chat.id, texts.diff_off).await?;
+            }
+        }
+    } else {
+        bot.send_message(msg.chat.id, texts.no_db).await?;
+    }
+
+    Ok(())
+}
+
+pub async fn weekdays_cmd(bot: Bot, msg: Message, lang: Lang) -> HandlerResult {
+    let texts = lang.texts();
+    if let Some(registration) = get_user_registration(msg.chat.id.0) {
+        bot.send_message(msg.chat.id, week_schedule_text(&registration))
+            .reply_markup(make_weekday_keyboard(&registration.schedule, lang))
+            .await?;
+    } else {
+        bot.send_message(msg.chat.id, texts.no_db).await?;
+    }
+
+    Ok(())
+}
+
+pub async fn skip_empty(bot: Bot, msg: Message, lang: Lang) -> HandlerResult {
+    let texts = lang.texts();
+    if let Some(mut registration) = get_user_registration(msg.chat.id.0) {
+        registration.settings.skip_empty = !registration.settings.skip_empty;
+
+        save_user_settings(msg.chat.id.0, &registration.settings).await?;
+        insert_user_registration(msg.chat.id.0, registration.clone());
+
+        match registration.settings.skip_empty {
+            true => {
+                bot.send_message(msg.chat.id, texts.skip_empty_on).await?;
+            }
+            false => {
+                bot.send_message(msg.chat.id, texts.skip_empty_off).await?;
             }
         }
     } else {
-        bot.send_message(msg.chat.id, NO_DB_MSG).await?;
+        bot.send_message(msg.chat.id, texts.no_db).await?;
+    }
+
+    Ok(())
+}
+
+pub async fn diet_cmd(bot: Bot, msg: Message, lang: Lang) -> HandlerResult {
+    let texts = lang.texts();
+    if let Some(registration) = get_user_registration(msg.chat.id.0) {
+        bot.send_message(msg.chat.id, texts.choose_diet)
+            .reply_markup(make_diet_keyboard(
+                registration.settings.diet,
+                registration.settings.diet_mode,
+                lang,
+            ))
+            .await?;
+    } else {
+        bot.send_message(msg.chat.id, texts.no_db).await?;
+    }
+
+    Ok(())
+}
+
+pub async fn allergen_profile_cmd(bot: Bot, msg: Message, lang: Lang) -> HandlerResult {
+    let texts = lang.texts();
+    if let Some(registration) = get_user_registration(msg.chat.id.0) {
+        bot.send_message(msg.chat.id, texts.choose_allergens)
+            .reply_markup(make_allergen_keyboard(
+                registration.settings.avoid_allergens,
+                registration.settings.allergen_mode,
+                lang,
+            ))
+            .await?;
+    } else {
+        bot.send_message(msg.chat.id, texts.no_db).await?;
+    }
+
+    Ok(())
+}
+
+pub async fn settings_cmd(bot: Bot, msg: Message, lang: Lang) -> HandlerResult {
+    let texts = lang.texts();
+    if let Some(registration) = get_user_registration(msg.chat.id.0) {
+        let (text, keyboard) = settings_page(&registration);
+        bot.send_message(msg.chat.id, text)
+            .reply_markup(keyboard)
+            .await?;
+    } else {
+        bot.send_message(msg.chat.id, texts.no_db).await?;
+    }
+
+    Ok(())
+}
+
+pub async fn price_tier_cmd(bot: Bot, msg: Message, lang: Lang) -> HandlerResult {
+    let texts = lang.texts();
+    if !BACKEND.get().unwrap().capabilities().price_tiers {
+        bot.send_message(msg.chat.id, texts.no_price_tiers).await?;
+    } else if let Some(registration) = get_user_registration(msg.chat.id.0) {
+        bot.send_message(msg.chat.id, texts.choose_price_tier)
+            .reply_markup(make_price_tier_keyboard(
+                registration.settings.price_tier,
+                lang,
+            ))
+            .await?;
+    } else {
+        bot.send_message(msg.chat.id, texts.no_db).await?;
+    }
+
+    Ok(())
+}
+
+pub async fn language_cmd(bot: Bot, msg: Message, lang: Lang) -> HandlerResult {
+    let texts = lang.texts();
+    if let Some(registration) = get_user_registration(msg.chat.id.0) {
+        bot.send_message(msg.chat.id, texts.choose_lang)
+            .reply_markup(make_lang_keyboard(registration.settings.lang))
+            .await?;
+    } else {
+        bot.send_message(msg.chat.id, texts.no_db).await?;
+    }
+
+    Ok(())
+}
+
+pub async fn watch_cmd(bot: Bot, msg: Message, keyword: String, lang: Lang) -> HandlerResult {
+    let texts = lang.texts();
+    if get_user_registration(msg.chat.id.0).is_none() {
+        bot.send_message(msg.chat.id, texts.no_db).await?;
+        return Ok(());
+    }
+
+    let keyword = keyword.trim();
+    let text = if keyword.is_empty() {
+        let watches = get_user_watches(msg.chat.id.0).await?;
+        if watches.is_empty() {
+            texts.watch_none_yet.to_string()
+        } else {
+            format!("{}\n{}", texts.watch_list, watches.join("\n"))
+        }
+    } else if keyword.len() > WATCH_KEYWORD_MAX_LEN {
+        (texts.watch_too_long)(WATCH_KEYWORD_MAX_LEN)
+    } else if add_watch(msg.chat.id.0, keyword).await? {
+        (texts.watch_added)(keyword)
+    } else {
+        (texts.watch_exists)(keyword)
+    };
+
+    bot.send_message(msg.chat.id, text).await?;
+
+    Ok(())
+}
+
+pub async fn unwatch_cmd(bot: Bot, msg: Message, keyword: String, lang: Lang) -> HandlerResult {
+    let texts = lang.texts();
+    let keyword = keyword.trim();
+    if keyword.is_empty() {
+        let watches = get_user_watches(msg.chat.id.0).await?;
+        if watches.is_empty() {
+            bot.send_message(msg.chat.id, texts.watch_none).await?;
+        } else {
+            bot.send_message(msg.chat.id, texts.watch_which)
+                .reply_markup(make_unwatch_keyboard(&watches))
+                .await?;
+        }
+    } else {
+        let text = match remove_watch(msg.chat.id.0, keyword).await? {
+            true => (texts.unwatched)(keyword),
+            false => (texts.not_watched)(keyword),
+        };
+        bot.send_message(msg.chat.id, text).await?;
     }
 
     Ok(())
@@ -203,9 +447,12 @@ pub async fn start_time_dialogue(
     msg: Message,
     dialogue: DialogueType,
     jobhandler_task_tx: broadcast::Sender<JobHandlerTask>,
+    lang: Lang,
 ) -> HandlerResult {
     if get_user_registration(msg.chat.id.0).is_none() {
-        bot.send_message(msg.chat.id, NO_DB_MSG).await.unwrap();
+        bot.send_message(msg.chat.id, lang.texts().no_db)
+            .await
+            .unwrap();
         dialogue.exit().await.unwrap();
         return Ok(());
     }
@@ -215,7 +462,21 @@ pub async fn start_time_dialogue(
         .split_once(' ')
         .map(|slices| slices.1.trim());
 
-    match parse_time_send_status_msgs(&bot, msg.chat.id, argument).await {
+    // "/uhrzeit Mo 07:00" only changes the time of that weekday
+    if let Some((weekday, time)) =
+        argument
+            .and_then(|arg| arg.split_once(' '))
+            .and_then(|(day, time)| {
+                // German names first, chrono knows the English ones ("mon", "monday")
+                let weekday = parse_german_weekday(&day.to_lowercase())
+                    .or_else(|| day.parse::<chrono::Weekday>().ok())?;
+                Some((weekday, time.trim()))
+            })
+    {
+        return set_weekday_time(&bot, msg.chat.id, weekday, time, jobhandler_task_tx, lang).await;
+    }
+
+    match parse_time_send_status_msgs(&bot, msg.chat.id, argument, lang).await {
         Ok(parsed_time) => {
             jobhandler_task_tx
                 .send(
@@ -241,23 +502,39 @@ pub async fn start_time_dialogue(
     Ok(())
 }
 
+pub async fn cancel_cmd(
+    bot: Bot,
+    msg: Message,
+    dialogue: DialogueType,
+    lang: Lang,
+) -> HandlerResult {
+    let texts = lang.texts();
+    let text = match dialogue.get().await? {
+        Some(state) if state != DialogueState::Default => {
+            dialogue.exit().await?;
+            texts.cancelled
+        }
+        _ => texts.nothing_to_cancel,
+    };
+    bot.send_message(msg.chat.id, text).await?;
+
+    Ok(())
+}
+
 pub async fn reply_time_dialogue(
     bot: Bot,
     msg: Message,
     dialogue: DialogueType,
     jobhandler_task_tx: broadcast::Sender<JobHandlerTask>,
+    lang: Lang,
 ) -> HandlerResult {
     if msg.text().is_none() {
-        bot.send_message(
-            msg.chat.id,
-            "Das ist kein Text.\nBitte mit Uhrzeit antworten:",
-        )
-        .await?;
+        bot.send_message(msg.chat.id, lang.texts().not_text).await?;
         return Ok(());
     }
 
     if let Ok(parsed_thing) =
-        parse_time_send_status_msgs(&bot, msg.chat.id, Some(msg.text().unwrap())).await
+        parse_time_send_status_msgs(&bot, msg.chat.id, Some(msg.text().unwrap()), lang).await
     {
         dialogue.exit().await.unwrap();
 
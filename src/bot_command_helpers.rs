@@ -1,56 +1,139 @@
-use crate::constants::{NO_DB_MSG, OLLAMA_HOST, OLLAMA_MODEL};
+use crate::constants::{OLLAMA_HOST, OLLAMA_MODEL};
 use crate::data_types::{
-    HandlerResult, MensaKeyboardAction, ParsedTimeAndLastMsgFromDialleougueue, TimeParseError,
+    HandlerResult, JobHandlerTask, MensaKeyboardAction, ParsedTimeAndLastMsgFromDialleougueue,
+    TimeParseError, UpdateScheduleTask,
 };
 
-use crate::shared_main::{get_user_registration, make_mensa_keyboard};
+use crate::db_operations::save_user_schedule;
+use crate::i18n::Lang;
+use crate::shared_main::{
+    get_user_registration, insert_user_registration, make_mensa_keyboard, week_schedule_text,
+};
 
 use regex_lite::Regex;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use static_init::dynamic;
-use teloxide::types::InputFile;
+use teloxide::types::{Chat, InputFile};
 
+use chrono::Weekday;
 use std::collections::BTreeMap;
 use teloxide::prelude::*;
+use tokio::sync::broadcast;
 
 pub async fn mensa_disp_or_upd(
     bot: Bot,
     msg: Message,
     mensen: BTreeMap<u32, String>,
     disp_or_update: MensaKeyboardAction,
+    lang: Lang,
 ) -> HandlerResult {
+    let texts = lang.texts();
     if get_user_registration(msg.chat.id.0).is_some() {
         let keyboard = make_mensa_keyboard(mensen, disp_or_update);
-        bot.send_message(msg.chat.id, "Mensa auswählen:")
+        bot.send_message(msg.chat.id, texts.choose_mensa)
             .reply_markup(keyboard)
             .await?;
     } else {
-        bot.send_message(msg.chat.id, NO_DB_MSG).await?;
+        bot.send_message(msg.chat.id, texts.no_db).await?;
     }
     Ok(())
 }
 
+/// Private chats always may, in groups only admins may change the settings
+pub async fn is_chat_admin(bot: &Bot, chat: &Chat, user_id: UserId) -> bool {
+    if chat.is_private() {
+        return true;
+    }
+
+    match bot.get_chat_member(chat.id, user_id).await {
+        Ok(member) => member.is_privileged(),
+        Err(e) => {
+            log::warn!(
+                "Checking chat member {} in {} failed: {}",
+                user_id,
+                chat.id,
+                e
+            );
+            false
+        }
+    }
+}
+
+pub async fn sender_is_admin(bot: &Bot, msg: &Message) -> bool {
+    // anonymous admins send on behalf of the group itself
+    if msg
+        .sender_chat
+        .as_ref()
+        .is_some_and(|sender| sender.id == msg.chat.id)
+    {
+        return true;
+    }
+
+    match &msg.from {
+        Some(user) => is_chat_admin(bot, &msg.chat, user.id).await,
+        None => false,
+    }
+}
+
+/// Sets (or with "standard"/"default" resets) the send time of a single weekday, enabling that day
+pub async fn set_weekday_time(
+    bot: &Bot,
+    chatid: ChatId,
+    weekday: Weekday,
+    time: &str,
+    jobhandler_task_tx: broadcast::Sender<JobHandlerTask>,
+    lang: Lang,
+) -> HandlerResult {
+    let Some(mut registration) = get_user_registration(chatid.0) else {
+        bot.send_message(chatid, lang.texts().no_db).await?;
+        return Ok(());
+    };
+    let weekday = weekday.num_days_from_monday();
+
+    if time.eq_ignore_ascii_case("standard") || time.eq_ignore_ascii_case("default") {
+        registration.schedule.day_times.remove(&weekday);
+    } else if let Ok(day_time) = rex_parse_time(time) {
+        registration.schedule.day_times.insert(weekday, day_time);
+        if !registration.schedule.is_enabled(weekday) {
+            registration.schedule.toggle(weekday);
+        }
+    } else {
+        bot.send_message(chatid, lang.texts().weekday_time_invalid)
+            .await?;
+        return Ok(());
+    }
+
+    save_user_schedule(chatid.0, &registration.schedule).await?;
+    bot.send_message(chatid, week_schedule_text(&registration))
+        .await?;
+
+    insert_user_registration(chatid.0, registration);
+    jobhandler_task_tx
+        .send(UpdateScheduleTask { chat_id: chatid.0 }.into())
+        .unwrap();
+
+    Ok(())
+}
+
 pub async fn parse_time_send_status_msgs(
     bot: &Bot,
     chatid: ChatId,
     txt: Option<&str>,
+    lang: Lang,
 ) -> Result<ParsedTimeAndLastMsgFromDialleougueue, TimeParseError> {
+    let texts = lang.texts();
     if txt.is_none() {
-        bot.send_message(chatid, "Bitte mit Uhrzeit antworten:")
+        bot.send_message(chatid, texts.reply_with_time)
             .await
             .unwrap();
         return Err(TimeParseError::NoTimeArgPassed);
     }
 
     if let Ok((hour, minute)) = rex_parse_time(txt.unwrap()) {
-        let msgtxt = format!(
-            "Plan wird ab jetzt automatisch an Wochentagen {:02}:{:02} Uhr \
-        gesendet.\n\n/unsubscribe zum Deaktivieren",
-            hour, minute
-        );
-
-        bot.send_message(chatid, msgtxt).await.unwrap();
+        bot.send_message(chatid, (texts.auto_time_set)(hour, minute))
+            .await
+            .unwrap();
 
         Ok(ParsedTimeAndLastMsgFromDialleougueue {
             hour,
@@ -60,7 +143,7 @@ pub async fn parse_time_send_status_msgs(
     } else {
         // try ai
         let oracle_msgid = bot
-            .send_message(chatid, "Das Orakel wird befragt...")
+            .send_message(chatid, texts.oracle_asking)
             .await
             .unwrap()
             .id;
@@ -69,15 +152,13 @@ pub async fn parse_time_send_status_msgs(
 
         match parse_result {
             Ok(parsed) => {
-                let msgtxt = format!(
-                    "Plan wird ab jetzt automatisch an Wochentagen {:02}:{:02} Uhr \
-                gesendet.\n\n/unsubscribe zum Deaktivieren",
-                    parsed.0, parsed.1
-                );
-
-                bot.edit_message_text(chatid, oracle_msgid, msgtxt)
-                    .await
-                    .unwrap();
+                bot.edit_message_text(
+                    chatid,
+                    oracle_msgid,
+                    (texts.auto_time_set)(parsed.0, parsed.1),
+                )
+                .await
+                .unwrap();
 
                 Ok(ParsedTimeAndLastMsgFromDialleougueue {
                     hour: parsed.0,
@@ -86,35 +167,23 @@ pub async fn parse_time_send_status_msgs(
                 })
             }
             Err(TimeParseError::InvalidTimePassed) => {
-                bot.edit_message_text(
-                    chatid,
-                    oracle_msgid,
-                    "Das Orakel verharrt in Ungewissheit.\nBitte erneut:",
-                )
-                .await
-                .unwrap();
+                bot.edit_message_text(chatid, oracle_msgid, texts.oracle_uncertain)
+                    .await
+                    .unwrap();
 
                 Err(TimeParseError::InvalidTimePassed)
             }
             Err(TimeParseError::OllamaUnavailable) => {
-                bot.edit_message_text(
-                    chatid,
-                    oracle_msgid,
-                    "Das Orakel erhört unsere Bitten nicht.\nBitte im Format HH:mm antworten:",
-                )
-                .await
-                .unwrap();
+                bot.edit_message_text(chatid, oracle_msgid, texts.oracle_unavailable)
+                    .await
+                    .unwrap();
 
                 Err(TimeParseError::OllamaUnavailable)
             }
             Err(TimeParseError::OllamaUnconfigured) => {
-                bot.edit_message_text(
-                    chatid,
-                    oracle_msgid,
-                    "Eingegebene Zeit ist ungültig.\nBitte mit Uhrzeit antworten:",
-                )
-                .await
-                .unwrap();
+                bot.edit_message_text(chatid, oracle_msgid, texts.time_invalid)
+                    .await
+                    .unwrap();
 
                 Err(TimeParseError::OllamaUnconfigured)
             }
@@ -1,19 +1,39 @@
 use std::{
     collections::BTreeMap,
-    sync::{OnceLock, RwLock},
+    sync::{Mutex, OnceLock, RwLock},
 };
 
-use crate::data_types::{Backend, CampusDualData, RegistrationEntry};
+use crate::{
+    data_backend::MealBackend,
+    data_types::{CampusDualData, RegistrationEntry},
+};
 
 pub static API_URL: OnceLock<String> = OnceLock::new();
+pub static MEAL_CACHE_TTL: OnceLock<chrono::Duration> = OnceLock::new();
+/// canteen names of the backend, fetched at startup
+pub static MENSEN: OnceLock<BTreeMap<u32, String>> = OnceLock::new();
 pub static USER_REGISTRATIONS: OnceLock<RwLock<BTreeMap<i64, RegistrationEntry>>> = OnceLock::new();
+/// chats that may use /stats, /broadcast and /user
+pub static ADMIN_CHAT_IDS: OnceLock<Vec<i64>> = OnceLock::new();
 
-pub const NO_DB_MSG: &str = "Bitte zuerst /start ausführen";
+// from this hour on fridays, /woche shows the next week
+pub const WEEK_ROLLOVER_HOUR: u32 = 15;
+// days ahead that are scanned for watched meals (/merken)
+pub const WATCH_SCAN_DAYS: i64 = 7;
+// open dialogues (e.g. waiting for a time) are reset after this
+pub const DIALOGUE_TIMEOUT_MINS: i64 = 30;
+// in bytes, /vergessen puts the keyword into callback data (max. 64 bytes)
+pub const WATCH_KEYWORD_MAX_LEN: usize = 40;
+// every inline result fetches a meal plan, so keep this low
+pub const INLINE_MAX_RESULTS: usize = 5;
 pub const MENSI_DB: &str = "mensimates.sqlite";
 pub const STUWE_DB: &str = "stuwe.sqlite";
+pub const OPENMENSA_DB: &str = "openmensa.sqlite";
 
 pub static DB_FILENAME: OnceLock<&str> = OnceLock::new();
-pub static BACKEND: OnceLock<Backend> = OnceLock::new();
+/// shared connection, only used through `db_operations`
+pub static DB: OnceLock<Mutex<rusqlite::Connection>> = OnceLock::new();
+pub static BACKEND: OnceLock<Box<dyn MealBackend>> = OnceLock::new();
 pub static CD_DATA: OnceLock<CampusDualData> = OnceLock::new();
 
 pub static OLLAMA_HOST: OnceLock<Option<String>> = OnceLock::new();
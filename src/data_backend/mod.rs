@@ -1,18 +1,515 @@
-use chrono::{Datelike, NaiveDate};
+use std::{collections::BTreeMap, fmt::Debug, future::Future, pin::Pin};
+
+use anyhow::Result;
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, Weekday};
+use rand::Rng;
+use teloxide::utils::markdown;
+use tokio::{sync::broadcast::Sender, time::Instant};
+
+use crate::{
+    allergen_parser::parse_allergens,
+    constants::{BACKEND, MEAL_CACHE_TTL, MENSEN},
+    data_types::{
+        meal_data_types::{MealGroup, SingleMeal},
+        AllergenMode, Diet, DietMode, JobHandlerTask, PriceTier, RegistrationEntry,
+    },
+    db_operations::{get_cached_meals, save_cached_meals},
+    i18n::{date_fmt, Lang},
+    price_parser::{format_cents, MealPrices},
+};
 
 pub mod mm_parser;
+pub mod openmensa_parser;
+pub mod stuwe_html_parser;
 pub mod stuwe_parser;
 
 const EMOJIS: [&str; 7] = ["☀️", "🦀", "💂🏻‍♀️", "☕️", "☝🏻", "🌤️", "🥦"];
 
-fn german_date_fmt(date: NaiveDate) -> String {
-    let week_days = ["Montag", "Dienstag", "Mittwoch", "Donnerstag", "Freitag"];
+pub type BackendFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T>> + Send + 'a>>;
+
+#[derive(Debug, Copy, Clone, Default)]
+pub struct BackendCapabilities {
+    /// meals come with user ratings
+    pub ratings: bool,
+    /// plan changes are pushed by the backend (see `MealBackend::listen_for_updates`)
+    pub diffs: bool,
+    /// prices contain separate student/staff/guest tiers
+    pub price_tiers: bool,
+}
+
+/// A source of canteens and meal plans.
+///
+/// Handlers, the scheduler and the message renderer only talk to this trait
+/// (through `BACKEND`), so a new data source only has to implement it.
+pub trait MealBackend: Debug + Send + Sync {
+    /// All canteens of this backend (id -> name)
+    fn get_mensen(&self) -> BackendFuture<'_, BTreeMap<u32, String>>;
+
+    /// Meals of one canteen at one day, empty if there is no plan
+    fn get_meals(&self, mensa_id: u32, date: NaiveDate) -> BackendFuture<'_, Vec<MealGroup>>;
+
+    /// Unique short name, e.g. used as meal cache key
+    fn name(&self) -> &'static str;
+
+    fn capabilities(&self) -> BackendCapabilities;
+
+    /// Runs until the update connection is lost, sending a `BroadcastUpdateTask` per plan change.
+    /// Only called if `capabilities().diffs` is set.
+    fn listen_for_updates(&self, _job_handler_tx: Sender<JobHandlerTask>) -> BackendFuture<'_, ()> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+pub struct MealPlan {
+    pub meal_groups: Vec<MealGroup>,
+    /// fetch time of an older cached plan, used because the backend failed
+    pub stale_since: Option<DateTime<Local>>,
+}
+
+/// Meals from the cache if younger than `MEAL_CACHE_TTL`, else from the backend.
+/// If the backend fails, the last cached plan is returned regardless of its age.
+pub async fn get_meals_cached(mensa_id: u32, date: NaiveDate) -> Result<MealPlan> {
+    let backend = BACKEND.get().unwrap();
+
+    let cached = get_cached_meals(backend.name(), mensa_id, date)
+        .await
+        .unwrap_or_else(|e| {
+            log::error!("Reading meal cache failed: {}", e);
+            None
+        });
+
+    if let Some((fetched_at, meal_groups)) = &cached {
+        if Local::now() - *fetched_at < *MEAL_CACHE_TTL.get().unwrap() {
+            return Ok(MealPlan {
+                meal_groups: meal_groups.clone(),
+                stale_since: None,
+            });
+        }
+    }
+
+    match backend.get_meals(mensa_id, date).await {
+        Ok(meal_groups) => {
+            if let Err(e) = save_cached_meals(backend.name(), mensa_id, date, &meal_groups).await {
+                log::error!("Writing meal cache failed: {}", e);
+            }
+            Ok(MealPlan {
+                meal_groups,
+                stale_since: None,
+            })
+        }
+        Err(e) => match cached {
+            Some((fetched_at, meal_groups)) => {
+                log::warn!("Meal fetch failed, using cache from {}: {}", fetched_at, e);
+                Ok(MealPlan {
+                    meal_groups,
+                    stale_since: Some(fetched_at),
+                })
+            }
+            None => Err(e),
+        },
+    }
+}
+
+/// Whether any of the mensen has meals at `date`. Fetch errors count as meals,
+/// so a failing backend is reported instead of hidden.
+pub async fn any_meals(mensa_ids: &[u32], date: NaiveDate) -> bool {
+    for mensa_id in mensa_ids {
+        match get_meals_cached(*mensa_id, date).await {
+            Ok(meal_plan) if meal_plan.meal_groups.is_empty() => {}
+            _ => return true,
+        }
+    }
+    false
+}
+
+/// Date of "today + days_forward", weekends are moved to the following monday
+pub fn resolve_days_forward(days_forward: i64) -> NaiveDate {
+    let requested_date = Local::now().date_naive() + Duration::days(days_forward);
+
+    match requested_date.weekday() {
+        // sat -> change req_date to mon
+        Weekday::Sat => requested_date + Duration::days(2),
+        Weekday::Sun => requested_date + Duration::days(1),
+        // Any other weekday is fine, nothing to do
+        _ => requested_date,
+    }
+}
+
+/// Per-user settings affecting how meals are rendered
+#[derive(Debug, Copy, Clone, Default)]
+pub struct MealRenderOptions {
+    pub allergens: bool,
+    pub diet: Diet,
+    pub diet_mode: DietMode,
+    /// bitmask of `ALLERGENS` the user has to avoid
+    pub avoid_allergens: u16,
+    pub allergen_mode: AllergenMode,
+    pub price_tier: PriceTier,
+    pub lang: Lang,
+}
+
+impl From<&RegistrationEntry> for MealRenderOptions {
+    fn from(registration: &RegistrationEntry) -> Self {
+        MealRenderOptions {
+            allergens: registration.settings.allergens,
+            diet: registration.settings.diet,
+            diet_mode: registration.settings.diet_mode,
+            avoid_allergens: registration.settings.avoid_allergens,
+            allergen_mode: registration.settings.allergen_mode,
+            price_tier: registration.settings.price_tier,
+            lang: registration.settings.lang,
+        }
+    }
+}
+
+/// Plan of `requested_date`, with one section per mensa if more than one is passed
+pub async fn build_meal_msg(
+    requested_date: NaiveDate,
+    mensa_ids: &[u32],
+    options: &MealRenderOptions,
+) -> String {
+    let texts = options.lang.texts();
+    let mut msg: String = String::new();
+    // start message formatting
+    let rand_emoji = EMOJIS[rand::thread_rng().gen_range(0..EMOJIS.len())];
+    msg += &format!(
+        "{} {} {}\n",
+        rand_emoji,
+        date_fmt(requested_date, options.lang),
+        rand_emoji,
+    );
+
+    // clarify if monday's plan is shown on a weekend
+    let today = Local::now().date_naive();
+    match (today.weekday(), (requested_date - today).num_days()) {
+        (Weekday::Sun, 1) => msg += &markdown::italic(&format!("      {}\n", texts.tomorrow_note)),
+        (Weekday::Sat, 2) => msg += &markdown::italic(&format!("      {}\n", texts.day_after_note)),
+        _ => {}
+    }
+
+    for mensa_id in mensa_ids {
+        if mensa_ids.len() > 1 {
+            msg += &format!(
+                "\n🏫 {}\n",
+                markdown::bold(&markdown::underline(&mensa_name(*mensa_id)))
+            );
+        }
+
+        // retrieve meals
+        let now = Instant::now();
+        match get_meals_cached(*mensa_id, requested_date).await {
+            Err(e) => {
+                log::warn!("Meal fetch failed: {}", e);
+                if mensa_ids.len() == 1 {
+                    msg = texts.error_occurred.to_string()
+                } else {
+                    // other mensen may still work
+                    msg += &markdown::italic(&format!("{}\n", texts.error_occurred));
+                }
+            }
+            Ok(meal_plan) => {
+                log::debug!("Backend data: {:.2?}", now.elapsed());
+
+                if let Some(stale_since) = meal_plan.stale_since {
+                    msg += &markdown::italic(&format!(
+                        "{} {}\n",
+                        texts.update_failed_since,
+                        stale_fmt(stale_since)
+                    ));
+                }
+
+                let meals_msg = mealgroups_to_msg(&meal_plan.meal_groups, options);
+                if meal_plan.meal_groups.is_empty() {
+                    msg += &markdown::bold(&format!("\n{}\n", texts.no_data));
+                } else if meals_msg.is_empty() {
+                    // only the filters that hide meals
+                    let mut filters = vec![];
+                    if options.diet != Diet::All && options.diet_mode == DietMode::Hide {
+                        filters.push(options.diet.label(options.lang));
+                    }
+                    if options.avoid_allergens != 0 && options.allergen_mode == AllergenMode::Hide {
+                        filters.push(texts.allergen_profile);
+                    }
+                    msg += &markdown::bold(&match filters.is_empty() {
+                        true => format!("\n{}.\n", texts.no_matching_meals),
+                        false => {
+                            format!("\n{} ({}).\n", texts.no_matching_meals, filters.join(", "))
+                        }
+                    });
+                } else {
+                    msg += &meals_msg;
+                }
+            }
+        };
+    }
+
+    escape_markdown_v2(&msg)
+}
+
+/// Name of a mensa as listed by the backend, or its ID if unknown
+pub fn mensa_name(mensa_id: u32) -> String {
+    MENSEN
+        .get()
+        .and_then(|mensen| mensen.get(&mensa_id).cloned())
+        .unwrap_or_else(|| format!("Mensa {}", mensa_id))
+}
+
+pub(crate) fn mealgroups_to_msg(meal_groups: &[MealGroup], options: &MealRenderOptions) -> String {
+    let texts = options.lang.texts();
+    let wants_allergens = options.allergens;
+    let mut msg: String = String::new();
+
+    // loop over meal groups
+    for meal_group in meal_groups {
+        let sub_meals: Vec<(&SingleMeal, bool, Vec<&str>)> = meal_group
+            .sub_meals
+            .iter()
+            .map(|meal| {
+                (
+                    meal,
+                    meal_fits_diet(&meal_group.meal_type, meal, options.diet),
+                    avoided_allergens(meal.allergens.as_deref(), options),
+                )
+            })
+            .filter(|(_, fits, avoided)| {
+                (*fits || options.diet_mode == DietMode::Dim)
+                    && (avoided.is_empty() || options.allergen_mode == AllergenMode::Warn)
+            })
+            .collect();
+        if sub_meals.is_empty() {
+            continue;
+        }
+
+        let first_meal = sub_meals.first().unwrap().0;
+        let price_first_meal = price_text(first_meal, options.price_tier);
+        let price_is_shared = sub_meals.iter().all(|(item, _, _)| {
+            price_key(item, options.price_tier) == price_key(first_meal, options.price_tier)
+        });
+
+        // Bold type of meal (-group)
+        msg += &format!(
+            "\n{} {}\n",
+            get_mealgroup_icon(&meal_group.meal_type),
+            markdown::bold(&meal_group.meal_type)
+        );
+
+        // loop over meals in meal group
+        for (sub_meal, fits_diet, avoided) in &sub_meals {
+            // not matching the diet: only the struck through name
+            if !fits_diet {
+                msg += &format!(" • {}\n", markdown::strike(&sub_meal.name));
+                continue;
+            }
+
+            // underlined single or multiple meal name
+            if !(sub_meals.len() == 1 && sub_meal.name == meal_group.meal_type) {
+                msg += &format!(" • {}\n", markdown::underline(&sub_meal.name));
+            }
+            if !avoided.is_empty() {
+                msg += &format!("    ⚠️ {}\n", markdown::bold(&avoided.join(", ")));
+            }
 
-    format!(
-        "{}, {}",
-        week_days[date.weekday().num_days_from_monday() as usize],
-        date.format("%d.%m.%Y")
+            // loop over ingredients of meal
+            for ingredient in &sub_meal.additional_ingredients {
+                // appending ingredient to msg
+                msg += &format!("     + {}\n", markdown::italic(ingredient))
+            }
+            if wants_allergens {
+                if let Some(allergens) = sub_meal.allergens.as_ref() {
+                    msg += &format!("    ⓘ {}\n", allergens)
+                }
+            }
+            // appending price
+            if !price_is_shared {
+                msg += &format!("   {}\n", price_text(sub_meal, options.price_tier));
+            }
+
+            if let Some(rating) = sub_meal.rating {
+                msg += &format!(
+                    "    {}: {} ({})\n",
+                    texts.rating,
+                    float_rating_to_stars(rating.stars),
+                    rating.stars
+                );
+            }
+
+            if let Some(variations) = sub_meal.variations.as_ref() {
+                msg += &format!("   → {}\n", markdown::bold(texts.variations));
+                for variation in variations {
+                    let avoided =
+                        avoided_allergens(variation.allergens_and_add.as_deref(), options);
+                    if !avoided.is_empty() {
+                        if options.allergen_mode == AllergenMode::Hide {
+                            continue;
+                        }
+                        msg += &format!(
+                            "       • {} ⚠️ {}\n",
+                            markdown::italic(&variation.name),
+                            markdown::bold(&avoided.join(", "))
+                        );
+                    } else {
+                        msg += &format!("       • {}\n", markdown::italic(&variation.name));
+                    }
+                    if wants_allergens {
+                        if let Some(allergens_and_add) = variation.allergens_and_add.as_ref() {
+                            msg += &format!("         ⓘ {}\n", allergens_and_add)
+                        }
+                    }
+                }
+            }
+        }
+        if price_is_shared {
+            msg += &format!("  {}\n", price_first_meal);
+        }
+    }
+
+    msg
+}
+
+/// Price of the user's tier, or the whole price string if it can't be parsed
+fn price_text(meal: &SingleMeal, price_tier: PriceTier) -> String {
+    match meal.prices().and_then(|prices| prices.get(price_tier)) {
+        Some(cents) => format_cents(cents),
+        None => meal.price.clone(),
+    }
+}
+
+/// What a meal's price is compared by, follows `price_text`
+#[derive(PartialEq)]
+enum PriceKey<'a> {
+    /// price of the user's tier
+    Cents(u32),
+    /// all tiers, for `PriceTier::All`
+    Tiers(MealPrices),
+    Raw(&'a str),
+}
+
+/// Numeric prices if parseable (e.g. "3,20 €" equals "3.20€"), else the string
+fn price_key(meal: &SingleMeal, price_tier: PriceTier) -> PriceKey<'_> {
+    match (meal.prices(), price_tier) {
+        (Some(prices), PriceTier::All) => PriceKey::Tiers(prices),
+        (Some(prices), _) => match prices.get(price_tier) {
+            Some(cents) => PriceKey::Cents(cents),
+            None => PriceKey::Raw(&meal.price),
+        },
+        (None, _) => PriceKey::Raw(&meal.price),
+    }
+}
+
+/// Names of the avoided allergens in a raw allergen string
+fn avoided_allergens(raw: Option<&str>, options: &MealRenderOptions) -> Vec<&'static str> {
+    match (raw, options.avoid_allergens) {
+        (Some(raw), 1..) => parse_allergens(raw).matching_names(
+            options.avoid_allergens,
+            &options.lang.texts().allergen_names,
+        ),
+        _ => vec![],
+    }
+}
+
+/// Whether a meal fits the diet, judged by keywords of its group, name and ingredients.
+/// Meals without any hint (e.g. side dishes) are assumed to fit.
+pub fn meal_fits_diet(meal_type: &str, meal: &SingleMeal, diet: Diet) -> bool {
+    const FISH: [&str; 10] = [
+        "fisch",
+        "lachs",
+        "thunfisch",
+        "hering",
+        "forelle",
+        "kabeljau",
+        "scholle",
+        "pangasius",
+        "garnele",
+        "meeresfrüchte",
+    ];
+    const PORK: [&str; 7] = [
+        "schwein",
+        "speck",
+        "schinken",
+        "salami",
+        "bacon",
+        "leberkäse",
+        "kassler",
+    ];
+    // short words like "ente" or "lamm" would match polenta or flammkuchen
+    const MEAT: [&str; 11] = [
+        "fleisch",
+        "rind",
+        "hähnchen",
+        "huhn",
+        "pute",
+        "geflügel",
+        "wurst",
+        "kalb",
+        "gulasch",
+        "döner",
+        "chicken",
+    ];
+
+    let text = format!(
+        "{} {} {}",
+        meal_type,
+        meal.name,
+        meal.additional_ingredients.join(" ")
     )
+    .to_lowercase();
+    let mentions = |keywords: &[&str]| keywords.iter().any(|keyword| text.contains(keyword));
+
+    let vegan = text.contains("vegan");
+    let vegetarian = vegan || text.contains("vegetarisch");
+    let fish = !vegetarian && mentions(&FISH);
+    let pork = !vegetarian && mentions(&PORK);
+    let meat = !vegetarian && (pork || mentions(&MEAT));
+
+    match diet {
+        Diet::All => true,
+        Diet::Vegetarian => !meat && !fish,
+        Diet::Vegan => vegan || !(vegetarian || meat || fish),
+        Diet::NoFish => !fish,
+        Diet::NoPork => !pork,
+    }
+}
+
+fn get_mealgroup_icon(meal_name: &str) -> &'static str {
+    match meal_name.to_lowercase().as_str() {
+        s if s.contains("vegan") => "🌱",
+        s if s.contains("vegetarisch") => "🧀",
+        s if s.contains("fleisch") => "🍗",
+        s if s.contains("grill") => "🍔",
+        s if s.contains("fisch") => "🐟",
+        s if s.contains("pastateller") => "🍝",
+        s if s.contains("gemüse") => "🥕",
+        s if s.contains("sättigung") => "➕",
+        s if s.contains("schneller teller") => "♿️",
+        _ => "🍽️",
+    }
+}
+
+pub fn float_rating_to_stars(rating: f32) -> String {
+    let floor = rating.floor();
+    let partial_star = match rating - floor {
+        r if r >= 0.875 => '🌕',
+        r if r >= 0.625 => '🌖',
+        r if r >= 0.375 => '🌗',
+        r if r >= 0.125 => '🌘',
+        _ => '🌑',
+    };
+
+    let mut stars: String = "🌕".repeat(floor as usize);
+    if (floor as usize) < 5 {
+        stars.push(partial_star);
+        stars.push_str(&"🌑".repeat(5 - stars.chars().count()));
+    }
+
+    stars
+}
+
+fn stale_fmt(stale_since: DateTime<Local>) -> String {
+    if stale_since.date_naive() == Local::now().date_naive() {
+        stale_since.format("%H:%M").to_string()
+    } else {
+        stale_since.format("%d.%m. %H:%M").to_string()
+    }
 }
 
 fn escape_markdown_v2(input: &str) -> String {
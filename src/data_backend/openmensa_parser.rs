@@ -0,0 +1,344 @@
+use std::collections::BTreeMap;
+
+use anyhow::{bail, Context, Result};
+use chrono::NaiveDate;
+use roxmltree::{Document, Node, ParsingOptions};
+use tokio::sync::RwLock;
+
+use crate::data_backend::{BackendCapabilities, BackendFuture, MealBackend};
+use crate::data_types::meal_data_types::{MealGroup, SingleMeal};
+
+// order in which prices are joined into SingleMeal.price (student / staff / guest)
+const PRICE_ROLES: [&str; 3] = ["student", "employee", "other"];
+
+/// Backend for canteens publishing an [OpenMensa feed (v2)](https://doc.openmensa.org/feed/v2/).
+///
+/// Every source is a URL or local file path of a canteen metadata feed (`<canteen>` with `<name>`
+/// and `<feed><url>`), or of a meal feed directly. Canteen IDs are the 1-based position in the
+/// source list, so the order must not change between restarts.
+#[derive(Debug)]
+pub struct OpenMensaBackend {
+    sources: Vec<String>,
+    canteens: RwLock<BTreeMap<u32, OpenMensaCanteen>>,
+}
+
+#[derive(Debug, Clone)]
+struct OpenMensaCanteen {
+    name: String,
+    feed: String,
+}
+
+impl OpenMensaBackend {
+    pub fn new(sources: Vec<String>) -> Self {
+        OpenMensaBackend {
+            sources,
+            canteens: RwLock::new(BTreeMap::new()),
+        }
+    }
+
+    async fn load_canteens(&self) -> Result<BTreeMap<u32, OpenMensaCanteen>> {
+        let mut canteens = BTreeMap::new();
+
+        for (id, source) in (1..).zip(&self.sources) {
+            let xml = fetch_source(source).await?;
+            let (name, feed) = parse_canteen_metadata(&xml)
+                .with_context(|| format!("OpenMensa source {}", source))?;
+
+            canteens.insert(
+                id,
+                OpenMensaCanteen {
+                    name: name.unwrap_or_else(|| format!("Mensa {}", id)),
+                    // no <feed> -> source is the meal feed itself
+                    feed: feed.unwrap_or_else(|| source.clone()),
+                },
+            );
+        }
+
+        *self.canteens.write().await = canteens.clone();
+        Ok(canteens)
+    }
+
+    async fn get_canteen(&self, mensa_id: u32) -> Result<OpenMensaCanteen> {
+        if let Some(canteen) = self.canteens.read().await.get(&mensa_id) {
+            return Ok(canteen.clone());
+        }
+
+        self.load_canteens()
+            .await?
+            .remove(&mensa_id)
+            .with_context(|| format!("Unknown OpenMensa canteen {}", mensa_id))
+    }
+}
+
+impl MealBackend for OpenMensaBackend {
+    fn get_mensen(&self) -> BackendFuture<'_, BTreeMap<u32, String>> {
+        Box::pin(async {
+            let canteens = self.load_canteens().await?;
+            Ok(canteens.into_iter().map(|(id, c)| (id, c.name)).collect())
+        })
+    }
+
+    fn get_meals(&self, mensa_id: u32, date: NaiveDate) -> BackendFuture<'_, Vec<MealGroup>> {
+        Box::pin(async move {
+            let canteen = self.get_canteen(mensa_id).await?;
+            let xml = fetch_source(&canteen.feed).await?;
+            parse_meal_feed(&xml, date)
+        })
+    }
+
+    fn name(&self) -> &'static str {
+        "openmensa"
+    }
+
+    fn capabilities(&self) -> BackendCapabilities {
+        BackendCapabilities {
+            ratings: false,
+            diffs: false,
+            price_tiers: true,
+        }
+    }
+}
+
+async fn fetch_source(source: &str) -> Result<String> {
+    if source.starts_with("http://") || source.starts_with("https://") {
+        Ok(reqwest::get(source)
+            .await?
+            .error_for_status()?
+            .text()
+            .await?)
+    } else {
+        let path = source.strip_prefix("file://").unwrap_or(source);
+        Ok(tokio::fs::read_to_string(path)
+            .await
+            .with_context(|| format!("Reading OpenMensa feed {}", path))?)
+    }
+}
+
+fn parse_openmensa_doc(xml: &str) -> Result<Document<'_>> {
+    let options = ParsingOptions {
+        allow_dtd: true,
+        ..ParsingOptions::default()
+    };
+    let document = Document::parse_with_options(xml, options).context("Invalid OpenMensa XML")?;
+
+    let root = document.root_element();
+    if !root.has_tag_name("openmensa") {
+        bail!("Not an OpenMensa feed: <openmensa> missing");
+    }
+    let version = root.attribute("version").unwrap_or_default();
+    if !version.starts_with('2') {
+        bail!("Unsupported OpenMensa feed version '{}'", version);
+    }
+
+    Ok(document)
+}
+
+/// Canteen name and URL of the full meal feed, if the document contains them
+pub fn parse_canteen_metadata(xml: &str) -> Result<(Option<String>, Option<String>)> {
+    let document = parse_openmensa_doc(xml)?;
+    let Some(canteen) = child(document.root_element(), "canteen") else {
+        return Ok((None, None));
+    };
+
+    let name = child(canteen, "name").map(element_text);
+
+    let feeds: Vec<Node> = children(canteen, "feed").collect();
+    let feed = feeds
+        .iter()
+        .find(|feed| feed.attribute("name") == Some("full"))
+        .or(feeds.first())
+        .and_then(|feed| child(*feed, "url"))
+        .map(element_text);
+
+    Ok((name, feed))
+}
+
+/// Meals of one day in an OpenMensa meal feed, empty if the day is missing or closed
+pub fn parse_meal_feed(xml: &str, date: NaiveDate) -> Result<Vec<MealGroup>> {
+    let document = parse_openmensa_doc(xml)?;
+
+    let date = date.format("%Y-%m-%d").to_string();
+    let Some(day) = child(document.root_element(), "canteen").and_then(|canteen| {
+        children(canteen, "day").find(|day| day.attribute("date") == Some(date.as_str()))
+    }) else {
+        return Ok(vec![]);
+    };
+
+    let mut meal_groups = Vec::new();
+    for category in children(day, "category") {
+        let mut sub_meals = Vec::new();
+
+        for meal in children(category, "meal") {
+            let Some(name) = child(meal, "name").map(element_text) else {
+                continue;
+            };
+
+            let notes: Vec<String> = children(meal, "note").map(element_text).collect();
+
+            let prices: BTreeMap<&str, String> = children(meal, "price")
+                .filter_map(|price| Some((price.attribute("role")?, element_text(price))))
+                .collect();
+
+            sub_meals.push(SingleMeal {
+                name,
+                additional_ingredients: vec![],
+                allergens: (!notes.is_empty()).then(|| notes.join(", ")),
+                variations: None,
+                price: join_prices(&prices),
+                rating: None,
+            });
+        }
+
+        if !sub_meals.is_empty() {
+            meal_groups.push(MealGroup {
+                meal_type: category.attribute("name").unwrap_or("Gerichte").to_string(),
+                sub_meals,
+            });
+        }
+    }
+
+    Ok(meal_groups)
+}
+
+// tag names are matched without namespace, feeds declare the OpenMensa one as default
+fn children<'a, 'input>(
+    node: Node<'a, 'input>,
+    name: &'static str,
+) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.children()
+        .filter(move |child| child.has_tag_name(name))
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &'static str) -> Option<Node<'a, 'input>> {
+    children(node, name).next()
+}
+
+/// Text incl. CDATA sections of an element
+fn element_text(element: Node) -> String {
+    element
+        .descendants()
+        .filter_map(|node| node.is_text().then(|| node.text()).flatten())
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+/// Prices in `PRICE_ROLES` order, missing roles as "-" so every price keeps its slot
+fn join_prices(prices: &BTreeMap<&str, String>) -> String {
+    let mut joined: Vec<String> = PRICE_ROLES
+        .iter()
+        .map(|role| match prices.get(role) {
+            Some(price) => format_price(price),
+            None => "-".to_string(),
+        })
+        .collect();
+
+    while joined.last().is_some_and(|price| price == "-") {
+        joined.pop();
+    }
+
+    joined.join(" / ")
+}
+
+fn format_price(price: &str) -> String {
+    match price.parse::<f64>() {
+        Ok(price) => format!("{:.2} €", price).replace('.', ","),
+        Err(_) => price.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const METADATA: &str = include_str!("../../tests/fixtures/openmensa/metadata.xml");
+    const MEALS: &str = include_str!("../../tests/fixtures/openmensa/meals.xml");
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 10, day).unwrap()
+    }
+
+    #[test]
+    fn metadata_prefers_full_feed() {
+        let (name, feed) = parse_canteen_metadata(METADATA).unwrap();
+
+        assert_eq!(name.as_deref(), Some("Mensa Reichenhainer Straße"));
+        assert_eq!(
+            feed.as_deref(),
+            Some("https://example.org/openmensa/reichenhainer/full.xml")
+        );
+    }
+
+    #[test]
+    fn meal_feed_as_source_has_no_feed_url() {
+        let (name, feed) = parse_canteen_metadata(MEALS).unwrap();
+
+        // CDATA
+        assert_eq!(name.as_deref(), Some("Mensa Straße der Nationen"));
+        assert_eq!(feed, None);
+    }
+
+    #[test]
+    fn meal_feed_day() {
+        let groups = parse_meal_feed(MEALS, date(24)).unwrap();
+
+        // categories without meals are left out
+        let titles: Vec<&str> = groups.iter().map(|g| g.meal_type.as_str()).collect();
+        assert_eq!(titles, ["Hauptgericht", "Dessert"]);
+
+        let chicken = &groups[0].sub_meals[0];
+        assert_eq!(
+            chicken.name,
+            r#"Hähnchenbrust "Toskana" & Rosmarinkartoffeln"#
+        );
+        assert_eq!(
+            chicken.allergens.as_deref(),
+            Some("Weizen (A1), Sellerie (I)")
+        );
+        assert_eq!(chicken.price, "2,90 € / 4,90 € / 6,20 €");
+
+        let dal = &groups[0].sub_meals[1];
+        assert_eq!(dal.name, "Linsen-Dal mit Reis");
+        assert_eq!(dal.allergens, None);
+        assert_eq!(dal.price, "2,50 € / - / 5,50 €");
+
+        // unknown roles (pupil) are ignored
+        assert_eq!(groups[1].sub_meals[0].price, "1,20 €");
+    }
+
+    #[test]
+    fn closed_and_missing_days_are_empty() {
+        assert!(parse_meal_feed(MEALS, date(25)).unwrap().is_empty());
+        assert!(parse_meal_feed(MEALS, date(26)).unwrap().is_empty());
+        assert!(parse_meal_feed(METADATA, date(24)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn rejects_other_documents() {
+        assert!(parse_meal_feed("<html><body/></html>", date(24)).is_err());
+        assert!(parse_meal_feed(r#"<openmensa version="1.0"/>"#, date(24)).is_err());
+        assert!(parse_meal_feed("<openmensa version=", date(24)).is_err());
+    }
+
+    #[test]
+    fn prices_keep_their_slot() {
+        let prices = |pairs: &[(&'static str, &str)]| -> BTreeMap<&'static str, String> {
+            pairs
+                .iter()
+                .map(|(role, price)| (*role, price.to_string()))
+                .collect()
+        };
+
+        assert_eq!(
+            join_prices(&prices(&[
+                ("other", "6.2"),
+                ("student", "2"),
+                ("employee", "4.90")
+            ])),
+            "2,00 € / 4,90 € / 6,20 €"
+        );
+        assert_eq!(join_prices(&prices(&[("employee", "4.90")])), "- / 4,90 €");
+        assert_eq!(join_prices(&prices(&[("student", "gratis")])), "gratis");
+        assert_eq!(join_prices(&prices(&[])), "");
+    }
+}
@@ -0,0 +1,296 @@
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use scraper::{ElementRef, Html, Selector};
+
+use crate::data_backend::{BackendCapabilities, BackendFuture, MealBackend};
+use crate::data_types::meal_data_types::{MealGroup, MealVariation, SingleMeal};
+
+const SPEISEPLAN_URL: &str = "https://www.studentenwerk-leipzig.de/mensen-cafeterien/speiseplan/";
+
+/// Backend scraping the Studentenwerk Leipzig meal plan website directly (no Mensa-API needed).
+///
+/// Canteen IDs are the website's location IDs, which Mensa-API uses as well,
+/// so registrations stay valid when switching between both StuWe backends.
+#[derive(Debug)]
+pub struct StuWeHtmlBackend;
+
+impl MealBackend for StuWeHtmlBackend {
+    fn get_mensen(&self) -> BackendFuture<'_, BTreeMap<u32, String>> {
+        Box::pin(async {
+            let html = reqwest::get(SPEISEPLAN_URL)
+                .await?
+                .error_for_status()?
+                .text()
+                .await?;
+            stuwe_html_to_mensen(&html)
+        })
+    }
+
+    fn get_meals(&self, mensa_id: u32, date: NaiveDate) -> BackendFuture<'_, Vec<MealGroup>> {
+        Box::pin(async move {
+            let html = reqwest::Client::new()
+                .get(SPEISEPLAN_URL)
+                .query(&[
+                    ("location", mensa_id.to_string()),
+                    ("date", date.format("%Y-%m-%d").to_string()),
+                ])
+                .send()
+                .await?
+                .error_for_status()?
+                .text()
+                .await?;
+            stuwe_html_to_mealgroups(&html, date)
+        })
+    }
+
+    fn name(&self) -> &'static str {
+        "stuwe-html"
+    }
+
+    fn capabilities(&self) -> BackendCapabilities {
+        BackendCapabilities {
+            ratings: false,
+            diffs: false,
+            price_tiers: true,
+        }
+    }
+}
+
+/// Canteens from the location dropdown of the meal plan page
+pub fn stuwe_html_to_mensen(html_text: &str) -> Result<BTreeMap<u32, String>> {
+    let document = Html::parse_document(html_text);
+    let option_sel = Selector::parse("select#edit-location > option").unwrap();
+
+    let mensen: BTreeMap<u32, String> = document
+        .select(&option_sel)
+        .filter_map(|option| {
+            let id = option.value().attr("value")?.parse::<u32>().ok()?;
+            Some((id, element_text(option)))
+        })
+        .collect();
+
+    if mensen.is_empty() {
+        anyhow::bail!("StuWe page: no canteens in location selection");
+    }
+
+    Ok(mensen)
+}
+
+/// Meals of the meal plan page of `requested_date`, empty if there is no plan for that day
+pub fn stuwe_html_to_mealgroups(
+    html_text: &str,
+    requested_date: NaiveDate,
+) -> Result<Vec<MealGroup>> {
+    let document = Html::parse_document(html_text);
+
+    // for days without a plan, the page silently shows the next available date
+    let date_sel = Selector::parse("select#edit-date > option[selected]").unwrap();
+    if let Some(shown_date) = document
+        .select(&date_sel)
+        .next()
+        .and_then(|option| option.value().attr("value"))
+    {
+        if shown_date != requested_date.format("%Y-%m-%d").to_string() {
+            return Ok(vec![]);
+        }
+    }
+
+    let Some(container) = document
+        .select(&Selector::parse("section.meals").unwrap())
+        .next()
+    else {
+        return Ok(vec![]);
+    };
+
+    let mut meal_groups: Vec<MealGroup> = Vec::new();
+
+    // children are a flat list: <h3> group title, followed by the meals of that group
+    for child in container.child_elements() {
+        if child.value().name() == "h3" {
+            meal_groups.push(MealGroup {
+                meal_type: element_text(child),
+                sub_meals: vec![],
+            });
+        } else if let Some(meal) = parse_single_meal(child) {
+            meal_groups
+                .last_mut()
+                .context("StuWe page: meal before first meal group title")?
+                .sub_meals
+                .push(meal);
+        }
+    }
+
+    meal_groups.retain(|group| !group.sub_meals.is_empty());
+
+    Ok(meal_groups)
+}
+
+fn parse_single_meal(element: ElementRef) -> Option<SingleMeal> {
+    let name = element
+        .select(&Selector::parse("h4").unwrap())
+        .next()
+        .map(element_text)?;
+
+    let additional_ingredients = element
+        .select(&Selector::parse("div.meal-components").unwrap())
+        .next()
+        .map(|components| {
+            element_text(components)
+                .split('·')
+                .map(|ingr| ingr.trim().to_string())
+                .filter(|ingr| !ingr.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let allergens = element
+        .select(&Selector::parse("div.meal-allergens > p").unwrap())
+        .next()
+        .map(element_text)
+        .filter(|allergens| !allergens.is_empty());
+
+    let variations: Vec<MealVariation> = element
+        .select(&Selector::parse("div.meal-subitems > p").unwrap())
+        .map(|variation| {
+            // variation allergens are in a nested element, if any
+            let allergens_and_add = variation
+                .select(&Selector::parse("span").unwrap())
+                .next()
+                .map(element_text);
+            let name = variation
+                .text()
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_string();
+
+            MealVariation {
+                name,
+                allergens_and_add,
+            }
+        })
+        .collect();
+
+    let price = element
+        .select(&Selector::parse("div.meal-prices > span").unwrap())
+        .map(element_text)
+        .collect::<Vec<String>>()
+        .join(" / ");
+
+    Some(SingleMeal {
+        name,
+        additional_ingredients,
+        allergens,
+        variations: (!variations.is_empty()).then_some(variations),
+        price,
+        rating: None,
+    })
+}
+
+fn element_text(element: ElementRef) -> String {
+    element
+        .text()
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: &str = include_str!("../../tests/fixtures/stuwe_html/day_2025-10-24.html");
+    const FALLBACK: &str = include_str!("../../tests/fixtures/stuwe_html/fallback_2025-10-25.html");
+    const NO_MEALS: &str = include_str!("../../tests/fixtures/stuwe_html/no_meals_2025-10-24.html");
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 10, day).unwrap()
+    }
+
+    #[test]
+    fn mensen_from_location_select() {
+        let mensen = stuwe_html_to_mensen(DAY).unwrap();
+
+        // the "Bitte wählen" placeholder has no id
+        assert_eq!(mensen.len(), 5);
+        assert_eq!(mensen[&111], "Mensa am Park");
+        assert_eq!(mensen[&118], "Mensa Peterssteinweg");
+    }
+
+    #[test]
+    fn mensen_missing_select_is_error() {
+        assert!(stuwe_html_to_mensen("<html><body></body></html>").is_err());
+    }
+
+    #[test]
+    fn normal_day() {
+        let groups = stuwe_html_to_mealgroups(DAY, date(24)).unwrap();
+
+        // "Dessert" only has a note, no meal
+        let titles: Vec<&str> = groups.iter().map(|g| g.meal_type.as_str()).collect();
+        assert_eq!(titles, ["Vegetarisches Gericht", "Pastateller"]);
+
+        let lasagne = &groups[0].sub_meals[0];
+        assert_eq!(lasagne.name, "Gemüselasagne mit Tomatensoße");
+        assert_eq!(
+            lasagne.additional_ingredients,
+            ["Blattsalat", "Joghurtdressing"]
+        );
+        assert_eq!(
+            lasagne.allergens.as_deref(),
+            Some("Weizen (A1), Milch (G), Sellerie (I)")
+        );
+        assert_eq!(lasagne.price, "2,90 € / 4,90 € / 6,20 €");
+        assert!(lasagne.variations.is_none());
+        assert!(lasagne.rating.is_none());
+    }
+
+    #[test]
+    fn meal_with_variations() {
+        let groups = stuwe_html_to_mealgroups(DAY, date(24)).unwrap();
+        let pasta = &groups[1].sub_meals[0];
+
+        assert_eq!(pasta.name, "Pasta nach Wahl");
+        assert!(pasta.allergens.is_none());
+        assert_eq!(pasta.price, "3,50 € / 5,50 € / 6,90 €");
+
+        let variations = pasta.variations.as_ref().unwrap();
+        let names: Vec<&str> = variations.iter().map(|v| v.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "Penne mit Bolognese",
+                "Spaghetti mit Pesto Genovese",
+                "Tagliatelle mit Gemüsesoße"
+            ]
+        );
+        assert_eq!(
+            variations[0].allergens_and_add.as_deref(),
+            Some("A1, C, I, 2")
+        );
+        assert_eq!(variations[2].allergens_and_add, None);
+    }
+
+    #[test]
+    fn next_available_date_is_no_plan() {
+        // requested saturday, the page shows monday instead
+        assert!(stuwe_html_to_mealgroups(FALLBACK, date(25))
+            .unwrap()
+            .is_empty());
+        // the shown date itself is parsed normally
+        assert_eq!(
+            stuwe_html_to_mealgroups(FALLBACK, date(27)).unwrap().len(),
+            2
+        );
+    }
+
+    #[test]
+    fn page_without_meals_section() {
+        assert!(stuwe_html_to_mealgroups(NO_MEALS, date(24))
+            .unwrap()
+            .is_empty());
+    }
+}
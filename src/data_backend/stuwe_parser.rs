@@ -1,199 +1,130 @@
 use std::collections::BTreeMap;
 
 use crate::constants::API_URL;
-use crate::data_backend::{escape_markdown_v2, german_date_fmt, EMOJIS};
-use crate::data_types::stuwe_data_types::{CanteenMealDiff, MealGroup};
+use crate::data_backend::{
+    escape_markdown_v2, meal_fits_diet, mealgroups_to_msg, BackendCapabilities, BackendFuture,
+    MealBackend, MealRenderOptions,
+};
+use crate::data_types::{
+    meal_data_types::MealGroup, stuwe_data_types::CanteenMealDiff, BroadcastUpdateTask,
+    JobHandlerTask,
+};
 
 use anyhow::Result;
-use chrono::{DateTime, Datelike, Duration, Local, Weekday};
-use rand::Rng;
+use chrono::NaiveDate;
+use futures_util::TryStreamExt;
+use reqwest::Client;
+use reqwest_websocket::{Message, RequestBuilderExt};
 use teloxide::utils::markdown;
+use tokio::sync::broadcast::Sender;
 
-use tokio::time::Instant;
-pub async fn stuwe_build_meal_msg(
-    days_forward: i64,
-    mensa_location: u32,
-    wants_allergens: bool,
-) -> String {
-    // get requested date
-    let mut requested_date = chrono::Local::now() + Duration::days(days_forward);
-    let mut date_raised_by_days = 0;
-
-    match requested_date.weekday() {
-        // sat -> change req_date to mon
-        Weekday::Sat => {
-            requested_date += Duration::days(2);
-            date_raised_by_days = 2;
-        }
-        Weekday::Sun => {
-            requested_date += Duration::days(1);
-            date_raised_by_days = 1;
-        }
-        _ => {
-            // Any other weekday is fine, nothing to do
-        }
+/// Backend for a separately hosted [Mensa-API](https://github.com/greybaron/mensa-api) (`API_URL`)
+#[derive(Debug)]
+pub struct StuWeBackend;
+
+impl MealBackend for StuWeBackend {
+    fn get_mensen(&self) -> BackendFuture<'_, BTreeMap<u32, String>> {
+        Box::pin(get_mensen())
     }
 
-    let mut msg: String = String::new();
-    // start message formatting
-    let rand_emoji = EMOJIS[rand::thread_rng().gen_range(0..EMOJIS.len())];
-    msg += &format!(
-        "{} {} {}\n",
-        rand_emoji,
-        german_date_fmt(requested_date.date_naive()),
-        rand_emoji,
-    );
-
-    // warn if requested "today" was raised to next monday (requested on sat/sun)
-    if days_forward == 0 && date_raised_by_days == 1 {
-        msg += &markdown::italic("      (Morgen)\n");
-    } else if days_forward == 0 && date_raised_by_days == 2 {
-        msg += &markdown::italic("      (Übermorgen)\n")
+    fn get_meals(&self, mensa_id: u32, date: NaiveDate) -> BackendFuture<'_, Vec<MealGroup>> {
+        Box::pin(get_meals_from_api(date, mensa_id))
     }
 
-    // retrieve meals
-    let now = Instant::now();
-    match get_meals_from_api(requested_date, mensa_location).await {
-        Err(_) => msg = "Ein Fehler ist aufgetreten.".to_string(),
-        Ok(meal_groups) => {
-            log::debug!("API data: {:.2?}", now.elapsed());
-
-            if meal_groups.is_empty() {
-                msg += &markdown::bold("\nkeine Daten vorhanden.\n");
-            } else {
-                msg += &mealgroups_to_msg(&meal_groups, wants_allergens);
-            }
+    fn name(&self) -> &'static str {
+        "stuwe"
+    }
+
+    fn capabilities(&self) -> BackendCapabilities {
+        BackendCapabilities {
+            ratings: false,
+            diffs: true,
+            price_tiers: true,
         }
-    };
+    }
 
-    escape_markdown_v2(&msg)
+    fn listen_for_updates(&self, job_handler_tx: Sender<JobHandlerTask>) -> BackendFuture<'_, ()> {
+        Box::pin(await_handle_mealplan_upd(job_handler_tx))
+    }
 }
 
-pub async fn stuwe_build_diff_msg(diff: &CanteenMealDiff, wants_allergens: bool) -> String {
-    let mut msg = markdown::bold(&markdown::underline("Planänderung")).to_string();
+/// Message of a plan change, `None` if the diet hides every changed meal
+pub async fn stuwe_build_diff_msg(
+    diff: &CanteenMealDiff,
+    mensa_name: Option<&str>,
+    options: &MealRenderOptions,
+) -> Option<String> {
+    let texts = options.lang.texts();
+    let mut msg = markdown::bold(&markdown::underline(texts.plan_changed)).to_string();
+    if let Some(mensa_name) = mensa_name {
+        msg += &format!(" {}", markdown::bold(mensa_name));
+    }
+    let mut has_changes = false;
+
     if let Some(new_meals) = diff.new_meals.as_ref() {
-        msg += &markdown::bold(&markdown::underline(if new_meals.len() == 1 {
-            "\nNeues Gericht:"
-        } else {
-            "\nNeue Gerichte:"
-        }));
-        msg += &mealgroups_to_msg(new_meals, wants_allergens);
+        let new_meals_msg = mealgroups_to_msg(new_meals, options);
+        if !new_meals_msg.is_empty() {
+            msg += &markdown::bold(&markdown::underline(&format!(
+                "\n{}",
+                texts.new_meals[usize::from(new_meals.len() > 1)]
+            )));
+            msg += &new_meals_msg;
+            has_changes = true;
+        }
     }
 
-    let use_modified = match wants_allergens {
+    let use_modified = match options.allergens {
         true => &diff.modified_meals,
         false => &diff.modified_meals_ignoring_allergens,
     };
 
     if let Some(modified_meals) = use_modified {
-        msg += &markdown::bold(&markdown::underline(if modified_meals.len() == 1 {
-            "\nGeändertes Gericht:"
-        } else {
-            "\nGeänderte Gerichte:"
-        }));
-        msg += &mealgroups_to_msg(modified_meals, wants_allergens);
-    }
-
-    if let Some(removed_meals) = diff.removed_meals.as_ref() {
-        msg += &markdown::bold(&markdown::underline(if removed_meals.len() == 1 {
-            "\nEntferntes Gericht:"
-        } else {
-            "\nEntfernte Gerichte:"
-        }));
-        for rem_meal in removed_meals {
-            for sub_meal in &rem_meal.sub_meals {
-                msg += &format!("\n • {}", markdown::underline(&sub_meal.name));
-            }
+        let modified_meals_msg = mealgroups_to_msg(modified_meals, options);
+        if !modified_meals_msg.is_empty() {
+            msg += &markdown::bold(&markdown::underline(&format!(
+                "\n{}",
+                texts.modified_meals[usize::from(modified_meals.len() > 1)]
+            )));
+            msg += &modified_meals_msg;
+            has_changes = true;
         }
     }
 
-    escape_markdown_v2(msg.trim_end())
-}
-
-fn mealgroups_to_msg(meal_groups: &[MealGroup], wants_allergens: bool) -> String {
-    let mut msg: String = String::new();
-
-    // loop over meal groups
-    for meal_group in meal_groups {
-        let price_first_meal = meal_group.sub_meals.first().unwrap().price.clone();
-        let price_is_shared = meal_group
-            .sub_meals
+    if let Some(removed_meals) = diff.removed_meals.as_ref() {
+        let removed_names: Vec<&str> = removed_meals
             .iter()
-            .all(|item| item.price == price_first_meal);
-
-        // Bold type of meal (-group)
-        msg += &format!(
-            "\n{} {}\n",
-            get_mealgroup_icon(&meal_group.meal_type),
-            markdown::bold(&meal_group.meal_type)
-        );
-
-        // loop over meals in meal group
-        for sub_meal in &meal_group.sub_meals {
-            // underlined single or multiple meal name
-            if !(meal_group.sub_meals.len() == 1 && sub_meal.name == meal_group.meal_type) {
-                msg += &format!(" • {}\n", markdown::underline(&sub_meal.name));
-            }
-
-            // loop over ingredients of meal
-            for ingredient in &sub_meal.additional_ingredients {
-                // appending ingredient to msg
-                msg += &format!("     + {}\n", markdown::italic(ingredient))
-            }
-            if wants_allergens {
-                if let Some(allergens) = sub_meal.allergens.as_ref() {
-                    msg += &format!("    ⓘ {}\n", allergens)
-                }
-            }
-            // appending price
-            if !price_is_shared {
-                msg += &format!("   {}\n", sub_meal.price);
-            }
-
-            if let Some(variations) = sub_meal.variations.as_ref() {
-                msg += &format!("   → {}\n", markdown::bold("Variationen:"));
-                for variation in variations {
-                    msg += &format!("       • {}\n", markdown::italic(&variation.name));
-                    if wants_allergens {
-                        if let Some(allergens_and_add) = variation.allergens_and_add.as_ref() {
-                            msg += &format!("         ⓘ {}\n", allergens_and_add)
-                        }
-                    }
-                }
+            .flat_map(|rem_meal| {
+                rem_meal
+                    .sub_meals
+                    .iter()
+                    .filter(|sub_meal| meal_fits_diet(&rem_meal.meal_type, sub_meal, options.diet))
+                    .map(|sub_meal| sub_meal.name.as_str())
+            })
+            .collect();
+
+        if !removed_names.is_empty() {
+            msg += &markdown::bold(&markdown::underline(&format!(
+                "\n{}",
+                texts.removed_meals[usize::from(removed_names.len() > 1)]
+            )));
+            for name in removed_names {
+                msg += &format!("\n • {}", markdown::underline(name));
             }
-        }
-        if price_is_shared {
-            msg += &format!("  {}\n", price_first_meal);
+            has_changes = true;
         }
     }
 
-    msg
-}
-
-fn get_mealgroup_icon(meal_name: &str) -> &'static str {
-    match meal_name.to_lowercase().as_str() {
-        s if s.contains("vegan") => "🌱",
-        s if s.contains("vegetarisch") => "🧀",
-        s if s.contains("fleisch") => "🍗",
-        s if s.contains("grill") => "🍔",
-        s if s.contains("fisch") => "🐟",
-        s if s.contains("pastateller") => "🍝",
-        s if s.contains("gemüse") => "🥕",
-        s if s.contains("sättigung") => "➕",
-        s if s.contains("schneller teller") => "♿️",
-        _ => "🍽️",
-    }
+    has_changes.then(|| escape_markdown_v2(msg.trim_end()))
 }
 
-async fn get_meals_from_api(requested_date: DateTime<Local>, mensa: u32) -> Result<Vec<MealGroup>> {
-    let date_str = build_date_string(requested_date);
+async fn get_meals_from_api(requested_date: NaiveDate, mensa: u32) -> Result<Vec<MealGroup>> {
     let client = reqwest::Client::new();
     let meal_groups = client
         .get(format!(
             "{}/canteens/{}/days/{}",
             API_URL.get().unwrap(),
             mensa,
-            date_str
+            requested_date.format("%Y-%m-%d")
         ))
         .send()
         .await?
@@ -203,17 +134,7 @@ async fn get_meals_from_api(requested_date: DateTime<Local>, mensa: u32) -> Resu
     Ok(meal_groups)
 }
 
-fn build_date_string(requested_date: DateTime<Local>) -> String {
-    let (year, month, day) = (
-        requested_date.year(),
-        requested_date.month(),
-        requested_date.day(),
-    );
-
-    format!("{:04}-{:02}-{:02}", year, month, day)
-}
-
-pub async fn get_mensen() -> Result<BTreeMap<u32, String>> {
+async fn get_mensen() -> Result<BTreeMap<u32, String>> {
     let client = reqwest::Client::new();
 
     #[derive(serde::Deserialize)]
@@ -231,3 +152,28 @@ pub async fn get_mensen() -> Result<BTreeMap<u32, String>> {
 
     Ok(mensa_list.into_iter().map(|m| (m.id, m.name)).collect())
 }
+
+async fn await_handle_mealplan_upd(job_handler_tx: Sender<JobHandlerTask>) -> Result<()> {
+    let response = Client::default()
+        .get(format!("{}/today_updated_diff_ws", API_URL.get().unwrap()))
+        .upgrade() // Prepares the WebSocket upgrade.
+        .send()
+        .await?;
+
+    // Turns the response into a WebSocket stream.
+    let mut websocket = response.into_websocket().await?;
+    log::info!("MensaUpdate WebSocket connected");
+
+    while let Some(message) = websocket.try_next().await? {
+        if let Message::Text(text) = message {
+            job_handler_tx.send(
+                BroadcastUpdateTask {
+                    meals_diff: serde_json::from_str(&text)?,
+                }
+                .into(),
+            )?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+
+use crate::price_parser::{parse_prices, MealPrices};
+
+// shared meal model, every backend maps its data into these
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MealGroup {
+    pub meal_type: String,
+    pub sub_meals: Vec<SingleMeal>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SingleMeal {
+    pub name: String,
+    pub additional_ingredients: Vec<String>,
+    pub allergens: Option<String>,
+    pub variations: Option<Vec<MealVariation>>,
+    pub price: String,
+    // not sent by Mensa-API, only backends with ratings fill this
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rating: Option<MealRating>,
+}
+
+impl SingleMeal {
+    /// `price` parsed into tiers, `None` if it contains no price
+    pub fn prices(&self) -> Option<MealPrices> {
+        parse_prices(&self.price)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MealVariation {
+    pub name: String,
+    pub allergens_and_add: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct MealRating {
+    pub stars: f32,
+    pub votes: i64,
+}
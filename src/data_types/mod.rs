@@ -1,57 +1,120 @@
+pub mod meal_data_types;
 pub mod mm_data_types;
 pub mod stuwe_data_types;
 
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 use stuwe_data_types::CanteenMealDiff;
-use teloxide::{
-    dispatching::dialogue::InMemStorage, prelude::Dialogue, types::MessageId,
-    utils::command::BotCommands,
-};
+use teloxide::{prelude::Dialogue, types::MessageId, utils::command::BotCommands};
 use thiserror::Error;
 use tokio::sync::broadcast;
 use uuid::Uuid;
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+use crate::{dialogue_storage::SqliteDialogueStorage, i18n::Lang};
+
+#[derive(Debug, Copy, Clone, PartialEq, clap::ValueEnum)]
 pub enum Backend {
-    MensiMates,
+    #[value(name = "stuwe")]
     StuWe,
+    #[value(name = "mensimates")]
+    MensiMates,
+    #[value(name = "stuwe-html")]
+    StuWeHtml,
+    #[value(name = "openmensa")]
+    OpenMensa,
 }
 
+/// Menu and help texts are in `i18n`, English names are aliases
 #[derive(BotCommands, Clone, Debug, PartialEq)]
 #[command(rename_rule = "lowercase")]
 pub enum Command {
-    #[command(description = "Gerichte für heute")]
+    #[command(aliases = ["today"], hide_aliases)]
     Heute,
-    #[command(description = "Gerichte für morgen\n")]
+    #[command(aliases = ["tomorrow"], hide_aliases)]
     Morgen,
     #[command(hide)]
     Übermorgen,
-    #[command(description = "Andere Mensa anzeigen")]
+    #[command(aliases = ["week"], hide_aliases)]
+    Woche,
+    #[command(aliases = ["day"], hide_aliases)]
+    Tag(String),
+    #[command(aliases = ["canteen"], hide_aliases)]
     Mensa,
-    #[command(description = "Mensa wechseln\n")]
+    #[command(aliases = ["other"], hide_aliases)]
     Andere,
-    #[command(description = "autom. Nachrichten aktivieren")]
+    #[command(aliases = ["settings"], hide_aliases)]
+    Einstellungen,
+    #[command(aliases = ["follow"], hide_aliases)]
+    Mensen,
     Subscribe,
-    #[command(description = "autom. Nachrichten deaktivieren")]
     Unsubscribe,
-    #[command(description = "Sendezeitpunkt ändern")]
+    #[command(aliases = ["time"], hide_aliases)]
     Uhrzeit,
-    #[command(hide)]
+    #[command(aliases = ["weekdays"], hide_aliases)]
+    Wochentage,
+    #[command(aliases = ["allergens"], hide_aliases)]
     Allergene,
-    #[command(description = "Bei Planänderungen nur Unterschied schicken")]
     Diff,
+    #[command(aliases = ["skipempty"], hide_aliases)]
+    Auslassen,
+    #[command(aliases = ["diet"], hide_aliases)]
+    Ernaehrung,
+    #[command(aliases = ["allergies"], hide_aliases)]
+    Allergieprofil,
+    #[command(aliases = ["prices"], hide_aliases)]
+    Preise,
+    #[command(aliases = ["watch"], hide_aliases)]
+    Merken(String),
+    #[command(aliases = ["unwatch"], hide_aliases)]
+    Vergessen(String),
+    #[command(aliases = ["language"], hide_aliases)]
+    Sprache,
+    #[command(aliases = ["cancel"], hide_aliases)]
+    Abbrechen,
     #[command(hide)]
     Start,
+    #[command(hide)]
+    Stats,
+    #[command(hide)]
+    Broadcast(String),
+    #[command(hide)]
+    User(String),
+}
+
+impl Command {
+    /// In group chats, only admins may use these
+    pub fn changes_settings(&self) -> bool {
+        !matches!(
+            self,
+            Command::Heute
+                | Command::Morgen
+                | Command::Übermorgen
+                | Command::Woche
+                | Command::Tag(_)
+                | Command::Andere
+                | Command::Einstellungen
+        )
+    }
+
+    /// Only for the chats in `ADMIN_CHAT_IDS`, unknown to everyone else
+    pub fn is_operator_only(&self) -> bool {
+        matches!(
+            self,
+            Command::Stats | Command::Broadcast(_) | Command::User(_)
+        )
+    }
 }
 
-#[derive(Clone, Default)]
+/// Stored as JSON by `SqliteDialogueStorage`
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
 pub enum DialogueState {
     #[default]
     Default,
     AwaitTimeReply,
 }
 
-pub type DialogueType = Dialogue<DialogueState, InMemStorage<DialogueState>>;
+pub type DialogueType = Dialogue<DialogueState, SqliteDialogueStorage>;
 pub type HandlerResult = Result<(), Box<dyn std::error::Error + Send + Sync>>;
 
 pub type JobHandlerTaskType = (
@@ -71,7 +134,9 @@ pub enum JobType {
     Register,
     DeleteRegistration,
     UpdateRegistration,
+    UpdateSchedule,
     BroadcastUpdate,
+    RemoveChat,
 }
 
 #[derive(Debug, Clone)]
@@ -82,6 +147,26 @@ pub struct JobHandlerTask {
     pub hour: Option<u32>,
     pub minute: Option<u32>,
     pub meals_diff: Option<CanteenMealDiff>,
+    /// language of a new registration
+    pub lang: Option<Lang>,
+}
+
+/// The chat blocked the bot, was deleted or removed the bot
+pub struct RemoveChatTask {
+    pub chat_id: i64,
+}
+impl From<RemoveChatTask> for JobHandlerTask {
+    fn from(job: RemoveChatTask) -> Self {
+        JobHandlerTask {
+            job_type: JobType::RemoveChat,
+            chat_id: Some(job.chat_id),
+            mensa_id: None,
+            hour: None,
+            minute: None,
+            meals_diff: None,
+            lang: None,
+        }
+    }
 }
 
 pub struct RegisterTask {
@@ -89,6 +174,7 @@ pub struct RegisterTask {
     pub mensa_id: u32,
     pub hour: u32,
     pub minute: u32,
+    pub lang: Lang,
 }
 impl From<RegisterTask> for JobHandlerTask {
     fn from(job: RegisterTask) -> Self {
@@ -99,6 +185,7 @@ impl From<RegisterTask> for JobHandlerTask {
             hour: Some(job.hour),
             minute: Some(job.minute),
             meals_diff: None,
+            lang: Some(job.lang),
         }
     }
 }
@@ -115,6 +202,25 @@ impl From<UnregisterTask> for JobHandlerTask {
             hour: None,
             minute: None,
             meals_diff: None,
+            lang: None,
+        }
+    }
+}
+
+/// Reloads the jobs of a chat after its `WeekSchedule` changed
+pub struct UpdateScheduleTask {
+    pub chat_id: i64,
+}
+impl From<UpdateScheduleTask> for JobHandlerTask {
+    fn from(job: UpdateScheduleTask) -> Self {
+        JobHandlerTask {
+            job_type: JobType::UpdateSchedule,
+            chat_id: Some(job.chat_id),
+            mensa_id: None,
+            hour: None,
+            minute: None,
+            meals_diff: None,
+            lang: None,
         }
     }
 }
@@ -135,6 +241,7 @@ impl From<UpdateRegistrationTask> for JobHandlerTask {
             hour: job.hour,
             minute: job.minute,
             meals_diff: None,
+            lang: None,
         }
     }
 }
@@ -151,18 +258,207 @@ impl From<BroadcastUpdateTask> for JobHandlerTask {
             hour: None,
             minute: None,
             meals_diff: Some(job.meals_diff),
+            lang: None,
         }
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+/// Meals a user wants to see, stored as its index in `Diet::ALL`
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub enum Diet {
+    #[default]
+    All,
+    Vegetarian,
+    Vegan,
+    NoFish,
+    NoPork,
+}
+
+impl Diet {
+    pub const ALL: [Diet; 5] = [
+        Diet::All,
+        Diet::Vegetarian,
+        Diet::Vegan,
+        Diet::NoFish,
+        Diet::NoPork,
+    ];
+
+    pub fn label(self, lang: Lang) -> &'static str {
+        lang.texts().diet_labels[usize::from(self.to_db())]
+    }
+
+    pub fn to_db(self) -> u8 {
+        Diet::ALL.iter().position(|diet| *diet == self).unwrap() as u8
+    }
+
+    pub fn from_db(value: u8) -> Self {
+        Diet::ALL
+            .get(usize::from(value))
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+/// Price a user wants to see, stored as its index in `PriceTier::ALL`
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub enum PriceTier {
+    #[default]
+    All,
+    Student,
+    Staff,
+    Guest,
+}
+
+impl PriceTier {
+    pub const ALL: [PriceTier; 4] = [
+        PriceTier::All,
+        PriceTier::Student,
+        PriceTier::Staff,
+        PriceTier::Guest,
+    ];
+
+    pub fn label(self, lang: Lang) -> &'static str {
+        lang.texts().price_tier_labels[usize::from(self.to_db())]
+    }
+
+    pub fn to_db(self) -> u8 {
+        PriceTier::ALL
+            .iter()
+            .position(|tier| *tier == self)
+            .unwrap() as u8
+    }
+
+    pub fn from_db(value: u8) -> Self {
+        PriceTier::ALL
+            .get(usize::from(value))
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+/// How meals not matching the diet are shown
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub enum DietMode {
+    #[default]
+    Hide,
+    /// only the struck through name
+    Dim,
+}
+
+/// How meals with avoided allergens are shown
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub enum AllergenMode {
+    #[default]
+    Warn,
+    Hide,
+}
+
+/// Per-user settings, stored in the user's `registrations` row.
+///
+/// Loaded and saved as a whole (`get_user_settings`, `save_user_settings`),
+/// so a new setting is just a new field and column.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UserSettings {
+    pub allergens: bool,
+    pub senddiff: bool,
+    /// no auto send if none of the mensen has meals
+    pub skip_empty: bool,
+    pub diet: Diet,
+    pub diet_mode: DietMode,
+    /// bitmask of `allergen_parser::ALLERGENS`
+    pub avoid_allergens: u16,
+    pub allergen_mode: AllergenMode,
+    pub price_tier: PriceTier,
+    pub lang: Lang,
+}
+
+impl Default for UserSettings {
+    fn default() -> Self {
+        // same as the column defaults
+        Self {
+            allergens: true,
+            senddiff: true,
+            skip_empty: false,
+            diet: Diet::default(),
+            diet_mode: DietMode::default(),
+            avoid_allergens: 0,
+            allergen_mode: AllergenMode::default(),
+            price_tier: PriceTier::default(),
+            lang: Lang::default(),
+        }
+    }
+}
+
+/// Delivery weekdays and weekday specific times, weekdays are 0 (monday) to 6 (sunday)
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeekSchedule {
+    /// bit n set = plan is sent on weekday n
+    pub weekdays: u8,
+    /// times differing from the registration's default time
+    pub day_times: BTreeMap<u32, (u32, u32)>,
+}
+
+impl Default for WeekSchedule {
+    fn default() -> Self {
+        WeekSchedule {
+            // monday to friday
+            weekdays: 0b0011111,
+            day_times: BTreeMap::new(),
+        }
+    }
+}
+
+impl WeekSchedule {
+    pub fn is_enabled(&self, weekday: u32) -> bool {
+        self.weekdays & (1 << weekday) != 0
+    }
+
+    pub fn toggle(&mut self, weekday: u32) {
+        self.weekdays ^= 1 << weekday;
+    }
+
+    /// Send time on `weekday` given the default time, `None` if the day is disabled
+    pub fn send_time(&self, weekday: u32, hour: u32, minute: u32) -> Option<(u32, u32)> {
+        self.is_enabled(weekday).then(|| {
+            self.day_times
+                .get(&weekday)
+                .copied()
+                .unwrap_or((hour, minute))
+        })
+    }
+
+    /// Enabled weekdays grouped by their send time
+    pub fn delivery_times(&self, hour: u32, minute: u32) -> BTreeMap<(u32, u32), Vec<u32>> {
+        let mut times: BTreeMap<(u32, u32), Vec<u32>> = BTreeMap::new();
+        for weekday in 0..7 {
+            if let Some(time) = self.send_time(weekday, hour, minute) {
+                times.entry(time).or_default().push(weekday);
+            }
+        }
+        times
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct RegistrationEntry {
-    pub job_uuid: Option<Uuid>,
+    /// one job per distinct send time
+    pub job_uuids: Vec<Uuid>,
     pub mensa_id: u32,
+    /// followed in addition to `mensa_id`, shown below it
+    pub additional_mensa_ids: Vec<u32>,
     pub hour: Option<u32>,
     pub minute: Option<u32>,
-    pub allergens: bool,
-    pub senddiff: bool,
+    pub settings: UserSettings,
+    pub schedule: WeekSchedule,
+}
+
+impl RegistrationEntry {
+    /// Main mensa followed by all additional ones
+    pub fn mensa_ids(&self) -> Vec<u32> {
+        let mut mensa_ids = vec![self.mensa_id];
+        mensa_ids.extend(&self.additional_mensa_ids);
+        mensa_ids
+    }
 }
 
 #[derive(Error, Debug, Clone)]
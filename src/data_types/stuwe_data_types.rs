@@ -1,10 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct MealGroup {
-    pub meal_type: String,
-    pub sub_meals: Vec<SingleMeal>,
-}
+use super::meal_data_types::MealGroup;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct CanteenMealsDay {
@@ -20,18 +16,3 @@ pub struct CanteenMealDiff {
     pub modified_meals_ignoring_allergens: Option<Vec<MealGroup>>,
     pub removed_meals: Option<Vec<MealGroup>>,
 }
-
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct SingleMeal {
-    pub name: String,
-    pub additional_ingredients: Vec<String>,
-    pub allergens: Option<String>,
-    pub variations: Option<Vec<MealVariation>>,
-    pub price: String,
-}
-
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct MealVariation {
-    pub name: String,
-    pub allergens_and_add: Option<String>,
-}
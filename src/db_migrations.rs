@@ -0,0 +1,232 @@
+use rusqlite::{params, Connection, Transaction};
+
+/// Schema migrations in order, `PRAGMA user_version` is the number of applied ones.
+///
+/// Only append new migrations, released ones must never change. Only
+/// `create_registrations` tolerates an existing table, databases from before
+/// versioning already have it.
+const MIGRATIONS: &[fn(&Transaction) -> rusqlite::Result<()>] = &[
+    create_registrations,
+    create_meal_cache,
+    create_additional_mensen,
+    add_weekday_schedule,
+    add_skip_empty,
+    add_diet,
+    add_allergen_profile,
+    create_watches,
+    add_price_tier,
+    create_dialogue_states,
+    add_language,
+];
+
+/// Schema version this binary creates and understands
+pub fn latest_schema_version() -> u32 {
+    MIGRATIONS.len() as u32
+}
+
+pub fn schema_version(conn: &Connection) -> rusqlite::Result<u32> {
+    conn.query_row("pragma user_version", [], |row| row.get(0))
+}
+
+/// Applies all pending migrations, each in its own transaction.
+///
+/// Returns the versions before and after. A database newer than this binary
+/// is left untouched, see `latest_schema_version`.
+pub fn migrate(conn: &mut Connection) -> rusqlite::Result<(u32, u32)> {
+    let initial_version = schema_version(conn)?;
+
+    for (version, migration) in MIGRATIONS.iter().enumerate().skip(initial_version as usize) {
+        let tx = conn.transaction()?;
+        migration(&tx)?;
+        // pragmas can't be bound, the version is our own number
+        tx.execute_batch(&format!("pragma user_version = {}", version + 1))?;
+        tx.commit()?;
+    }
+
+    Ok((initial_version, schema_version(conn)?))
+}
+
+fn create_registrations(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "create table if not exists registrations (
+        chat_id integer not null unique primary key,
+        mensa_id integer not null,
+        hour integer,
+        minute integer,
+        allergens BOOLEAN DEFAULT 1,
+        senddiff BOOLEAN DEFAULT 1
+        )",
+    )?;
+    // in case a table from before versioning lacks them
+    add_column_if_missing(tx, "registrations", "allergens", "BOOLEAN DEFAULT 1")?;
+    add_column_if_missing(tx, "registrations", "senddiff", "BOOLEAN DEFAULT 1")
+}
+
+fn create_meal_cache(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "create table meal_cache (
+        backend text not null,
+        canteen_id integer not null,
+        date text not null,
+        fetched_at integer not null,
+        meal_groups text not null,
+        primary key (backend, canteen_id, date)
+        )",
+    )
+}
+
+fn create_additional_mensen(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "create table additional_mensen (
+        chat_id integer not null,
+        mensa_id integer not null,
+        primary key (chat_id, mensa_id)
+        )",
+    )
+}
+
+fn add_weekday_schedule(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "alter table registrations add column weekdays integer DEFAULT 31;
+        create table weekday_times (
+        chat_id integer not null,
+        weekday integer not null,
+        hour integer not null,
+        minute integer not null,
+        primary key (chat_id, weekday)
+        )",
+    )
+}
+
+fn add_skip_empty(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch("alter table registrations add column skip_empty BOOLEAN DEFAULT 0")
+}
+
+fn add_diet(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "alter table registrations add column diet integer DEFAULT 0;
+        alter table registrations add column diet_dim BOOLEAN DEFAULT 0",
+    )
+}
+
+fn add_allergen_profile(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "alter table registrations add column avoid_allergens integer DEFAULT 0;
+        alter table registrations add column avoid_hide BOOLEAN DEFAULT 0",
+    )
+}
+
+fn create_watches(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "create table watches (
+        chat_id integer not null,
+        keyword text not null,
+        primary key (chat_id, keyword)
+        );
+        create table watch_notifications (
+        chat_id integer not null,
+        date text not null,
+        meal_name text not null,
+        primary key (chat_id, date, meal_name)
+        )",
+    )
+}
+
+fn add_price_tier(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch("alter table registrations add column price_tier integer DEFAULT 0")
+}
+
+fn create_dialogue_states(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "create table dialogue_states (
+        chat_id integer not null primary key,
+        state text not null,
+        updated_at integer not null
+        )",
+    )
+}
+
+fn add_language(tx: &Transaction) -> rusqlite::Result<()> {
+    // existing chats have been using the bot in German
+    tx.execute_batch("alter table registrations add column lang text not null default 'de'")
+}
+
+/// Only for `create_registrations`, the others know the schema they start from
+fn add_column_if_missing(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> rusqlite::Result<()> {
+    let exists = conn
+        .prepare(&format!(
+            "select 1 from pragma_table_info('{}') where name = ?1",
+            table
+        ))?
+        .exists(params![column])?;
+
+    if !exists {
+        conn.execute(
+            &format!("alter table {} add column {} {}", table, column, definition),
+            [],
+        )?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrates_baseline_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        // as created by the releases before versioning
+        conn.execute_batch(
+            "create table registrations (
+            chat_id integer not null unique primary key,
+            mensa_id integer not null,
+            hour integer,
+            minute integer,
+            allergens BOOLEAN DEFAULT 1,
+            senddiff BOOLEAN DEFAULT 1
+            );
+            insert into registrations values (42, 140, 6, 30, 0, 1)",
+        )
+        .unwrap();
+
+        assert_eq!(migrate(&mut conn).unwrap(), (0, latest_schema_version()));
+        assert_eq!(schema_version(&conn).unwrap(), latest_schema_version());
+
+        let row: (u32, u32, bool, u8, String) = conn
+            .query_row(
+                "select mensa_id, minute, allergens, weekdays, lang from registrations
+                where chat_id = 42",
+                [],
+                |row| {
+                    Ok((
+                        row.get(0)?,
+                        row.get(1)?,
+                        row.get(2)?,
+                        row.get(3)?,
+                        row.get(4)?,
+                    ))
+                },
+            )
+            .unwrap();
+        assert_eq!(row, (140, 30, false, 31, "de".to_string()));
+
+        // nothing left to do
+        assert_eq!(
+            migrate(&mut conn).unwrap(),
+            (latest_schema_version(), latest_schema_version())
+        );
+    }
+
+    #[test]
+    fn creates_empty_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        assert_eq!(migrate(&mut conn).unwrap(), (0, latest_schema_version()));
+    }
+}
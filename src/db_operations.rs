@@ -1,169 +1,598 @@
-use rusqlite::{params, Connection};
-use std::process::exit;
+use chrono::{DateTime, Duration, Local, NaiveDate, TimeZone};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use std::{collections::BTreeMap, process::exit, sync::Mutex};
 
 use crate::{
-    constants::DB_FILENAME,
-    data_types::{JobHandlerTask, UpdateRegistrationTask},
+    constants::{DB, DB_FILENAME},
+    data_types::{
+        meal_data_types::MealGroup, AllergenMode, DialogueState, Diet, DietMode, JobHandlerTask,
+        PriceTier, RegistrationEntry, UserSettings, WeekSchedule,
+    },
+    db_migrations::{latest_schema_version, migrate, schema_version},
+    i18n::Lang,
 };
 
-pub fn update_db_row(data: &JobHandlerTask) -> rusqlite::Result<()> {
-    let conn = Connection::open(DB_FILENAME.get().unwrap())?;
-
-    // could be better but eh
-    let mut update_mensa_stmt = conn.prepare_cached(
-        "UPDATE registrations
-            SET mensa_id = ?2
-            WHERE chat_id = ?1",
-    )?;
-
-    let mut upd_hour_stmt = conn.prepare_cached(
-        "UPDATE registrations
-            SET hour = ?2
-            WHERE chat_id = ?1",
-    )?;
-
-    let mut upd_min_stmt = conn.prepare_cached(
-        "UPDATE registrations
-            SET minute = ?2
-            WHERE chat_id = ?1",
-    )?;
-
-    if let Some(mensa_id) = data.mensa_id {
-        update_mensa_stmt.execute(params![data.chat_id, mensa_id])?;
-    };
+pub async fn update_db_row(data: &JobHandlerTask) -> rusqlite::Result<()> {
+    let data = data.clone();
+
+    run_db(move |conn| {
+        // could be better but eh
+        let mut update_mensa_stmt = conn.prepare_cached(
+            "UPDATE registrations
+                SET mensa_id = ?2
+                WHERE chat_id = ?1",
+        )?;
+
+        let mut upd_hour_stmt = conn.prepare_cached(
+            "UPDATE registrations
+                SET hour = ?2
+                WHERE chat_id = ?1",
+        )?;
+
+        let mut upd_min_stmt = conn.prepare_cached(
+            "UPDATE registrations
+                SET minute = ?2
+                WHERE chat_id = ?1",
+        )?;
+
+        if let Some(mensa_id) = data.mensa_id {
+            update_mensa_stmt.execute(params![data.chat_id, mensa_id])?;
+        };
+
+        if let Some(hour) = data.hour {
+            upd_hour_stmt.execute(params![data.chat_id, hour])?;
+        };
+
+        if let Some(minute) = data.minute {
+            upd_min_stmt.execute(params![data.chat_id, minute])?;
+        };
+
+        Ok(())
+    })
+    .await
+}
 
-    if let Some(hour) = data.hour {
-        upd_hour_stmt.execute(params![data.chat_id, hour])?;
-    };
+pub async fn task_db_kill_auto(chat_id: i64) -> rusqlite::Result<()> {
+    run_db(move |conn| {
+        let mut stmt = conn.prepare_cached(
+            "UPDATE registrations
+                SET hour = NULL, minute = NULL
+                WHERE chat_id = ?1",
+        )?;
 
-    if let Some(minute) = data.minute {
-        upd_min_stmt.execute(params![data.chat_id, minute])?;
-    };
+        stmt.execute(params![chat_id])?;
 
-    Ok(())
+        Ok(())
+    })
+    .await
 }
 
-pub fn task_db_kill_auto(chat_id: i64) -> rusqlite::Result<()> {
-    let conn = Connection::open(DB_FILENAME.get().unwrap())?;
-    let mut stmt = conn.prepare_cached(
-        "UPDATE registrations
-            SET hour = NULL, minute = NULL
-            WHERE chat_id = ?1",
-    )?;
-
-    stmt.execute(params![chat_id])?;
+/// Removes every row of a chat that is gone for good
+pub async fn delete_chat(chat_id: i64) -> rusqlite::Result<()> {
+    run_db(move |conn| {
+        let tx = conn.transaction()?;
+        for table in [
+            "registrations",
+            "additional_mensen",
+            "weekday_times",
+            "watches",
+            "watch_notifications",
+            "dialogue_states",
+        ] {
+            tx.execute(
+                &format!("delete from {} where chat_id = ?1", table),
+                params![chat_id],
+            )?;
+        }
 
-    Ok(())
+        tx.commit()
+    })
+    .await
 }
 
+/// Opens the shared connection (WAL mode) and migrates the schema
 pub fn check_or_create_db_tables() -> rusqlite::Result<()> {
     if DB_FILENAME.get().is_none() {
         log::error!("DB_FILENAME not set");
         exit(1);
     }
-    let conn = Connection::open(DB_FILENAME.get().unwrap())?;
-
-    // ensure db table exists
-    conn.prepare(
-        "create table if not exists registrations (
-        chat_id integer not null unique primary key,
-        mensa_id integer not null,
-        hour integer,
-        minute integer,
-        allergens BOOLEAN DEFAULT 1,
-        senddiff BOOLEAN DEFAULT 1
-        )",
-    )?
-    .execute([])?;
+    let mut conn = Connection::open(DB_FILENAME.get().unwrap())?;
+
+    // an older binary must not touch (or break) a schema it doesn't know
+    let version = schema_version(&conn)?;
+    if version > latest_schema_version() {
+        log::error!(
+            "DB schema version {} is newer than supported version {}, refusing to start",
+            version,
+            latest_schema_version()
+        );
+        exit(1);
+    }
+
+    let (from, to) = migrate(&mut conn)?;
+    if from != to {
+        log::info!("Migrated DB schema from version {} to {}", from, to);
+    }
+
+    // readers don't block the writer, fsync only at checkpoints
+    conn.pragma_update(None, "journal_mode", "WAL")?;
+    conn.pragma_update(None, "synchronous", "NORMAL")?;
+
+    if DB.set(Mutex::new(conn)).is_err() {
+        log::warn!("DB connection was already initialized");
+    }
 
     Ok(())
 }
 
-pub fn get_all_user_registrations_db() -> rusqlite::Result<Vec<JobHandlerTask>> {
-    let mut tasks: Vec<JobHandlerTask> = Vec::new();
-    let conn = Connection::open(DB_FILENAME.get().unwrap()).unwrap();
+/// Runs `f` with the shared connection on tokio's blocking thread pool,
+/// so queries never block the async worker threads.
+async fn run_db<T, F>(f: F) -> rusqlite::Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce(&mut Connection) -> rusqlite::Result<T> + Send + 'static,
+{
+    tokio::task::spawn_blocking(move || {
+        // a panicking query doesn't leave the connection in a broken state
+        let mut conn = DB
+            .get()
+            .expect("DB not initialized")
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        f(&mut conn)
+    })
+    .await
+    .expect("DB task panicked")
+}
 
-    let mut stmt =
-        conn.prepare_cached("SELECT chat_id, mensa_id, hour, minute FROM registrations")?;
+/// All registrations with their settings, schedules and additional mensen, without jobs
+pub async fn get_all_user_registrations_db() -> rusqlite::Result<BTreeMap<i64, RegistrationEntry>> {
+    run_db(move |conn| {
+        let mut stmt = conn.prepare_cached(&format!(
+            "select chat_id, mensa_id, hour, minute, weekdays, {} from registrations",
+            SETTINGS_COLUMNS
+        ))?;
+        let mut registrations = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get(0)?,
+                    RegistrationEntry {
+                        job_uuids: Vec::new(),
+                        mensa_id: row.get(1)?,
+                        additional_mensa_ids: Vec::new(),
+                        hour: row.get(2)?,
+                        minute: row.get(3)?,
+                        settings: user_settings_from_row(row, 5)?,
+                        schedule: WeekSchedule {
+                            weekdays: row.get(4)?,
+                            day_times: BTreeMap::new(),
+                        },
+                    },
+                ))
+            })?
+            .collect::<rusqlite::Result<BTreeMap<i64, RegistrationEntry>>>()?;
+
+        let mut stmt =
+            conn.prepare_cached("select chat_id, weekday, hour, minute from weekday_times")?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            if let Some(registration) = registrations.get_mut(&row.get(0)?) {
+                registration
+                    .schedule
+                    .day_times
+                    .insert(row.get(1)?, (row.get(2)?, row.get(3)?));
+            }
+        }
 
-    let job_iter = stmt.query_map([], |row| {
-        Ok(UpdateRegistrationTask {
-            chat_id: row.get(0)?,
-            mensa_id: row.get(1)?,
-            hour: row.get(2)?,
-            minute: row.get(3)?,
+        let mut stmt = conn.prepare_cached(
+            "select chat_id, mensa_id from additional_mensen order by chat_id, mensa_id",
+        )?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            if let Some(registration) = registrations.get_mut(&row.get(0)?) {
+                registration.additional_mensa_ids.push(row.get(1)?);
+            }
         }
-        .into())
-    })?;
 
-    for job in job_iter {
-        tasks.push(job.unwrap())
-    }
+        Ok(registrations)
+    })
+    .await
+}
 
-    Ok(tasks)
+pub async fn init_db_record(
+    job_handler_task: &JobHandlerTask,
+    settings: &UserSettings,
+) -> rusqlite::Result<()> {
+    let job_handler_task = job_handler_task.clone();
+    let settings = settings.clone();
+
+    run_db(move |conn| {
+        let mut stmt = conn.prepare_cached(
+            "replace into registrations (chat_id, mensa_id, hour, minute,
+                allergens, senddiff, skip_empty, diet, diet_dim, avoid_allergens, avoid_hide,
+                price_tier, lang)
+            values (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+        )?;
+
+        stmt.execute(params![
+            job_handler_task.chat_id,
+            job_handler_task.mensa_id,
+            job_handler_task.hour.unwrap(),
+            job_handler_task.minute.unwrap(),
+            settings.allergens,
+            settings.senddiff,
+            settings.skip_empty,
+            settings.diet.to_db(),
+            settings.diet_mode == DietMode::Dim,
+            settings.avoid_allergens,
+            settings.allergen_mode == AllergenMode::Hide,
+            settings.price_tier.to_db(),
+            settings.lang.code()
+        ])?;
+
+        Ok(())
+    })
+    .await
 }
 
-pub fn init_db_record(job_handler_task: &JobHandlerTask) -> rusqlite::Result<()> {
-    let conn = Connection::open(DB_FILENAME.get().unwrap())?;
-    let mut stmt = conn.prepare_cached(
-        "replace into registrations (chat_id, mensa_id, hour, minute)
-            values (?1, ?2, ?3, ?4)",
-    )?;
+pub async fn set_additional_mensa(
+    chat_id: i64,
+    mensa_id: u32,
+    follow: bool,
+) -> rusqlite::Result<()> {
+    run_db(move |conn| {
+        let mut stmt = match follow {
+            true => conn.prepare_cached(
+                "insert or ignore into additional_mensen (chat_id, mensa_id) values (?1, ?2)",
+            )?,
+            false => conn.prepare_cached(
+                "delete from additional_mensen where chat_id = ?1 and mensa_id = ?2",
+            )?,
+        };
+
+        stmt.execute(params![chat_id, mensa_id])?;
+
+        Ok(())
+    })
+    .await
+}
 
-    stmt.execute(params![
-        job_handler_task.chat_id,
-        job_handler_task.mensa_id,
-        job_handler_task.hour.unwrap(),
-        job_handler_task.minute.unwrap()
-    ])?;
+pub async fn save_user_schedule(chat_id: i64, schedule: &WeekSchedule) -> rusqlite::Result<()> {
+    let schedule = schedule.clone();
+
+    run_db(move |conn| {
+        let tx = conn.transaction()?;
+
+        tx.execute(
+            "update registrations set weekdays = ?2 where chat_id = ?1",
+            params![chat_id, schedule.weekdays],
+        )?;
+        tx.execute(
+            "delete from weekday_times where chat_id = ?1",
+            params![chat_id],
+        )?;
+        for (weekday, (hour, minute)) in &schedule.day_times {
+            tx.execute(
+                "insert into weekday_times (chat_id, weekday, hour, minute) values (?1, ?2, ?3, ?4)",
+                params![chat_id, weekday, hour, minute],
+            )?;
+        }
 
-    Ok(())
+        tx.commit()
+    })
+    .await
 }
 
-pub fn get_user_allergen_state(chat_id: i64) -> rusqlite::Result<bool> {
-    let conn = Connection::open(DB_FILENAME.get().unwrap()).unwrap();
+/// Columns read by `user_settings_from_row`, in this order
+const SETTINGS_COLUMNS: &str = "allergens, senddiff, skip_empty, diet, diet_dim, avoid_allergens,
+    avoid_hide, price_tier, lang";
+
+/// Maps the settings columns starting at index `first`
+fn user_settings_from_row(row: &Row, first: usize) -> rusqlite::Result<UserSettings> {
+    Ok(UserSettings {
+        allergens: row.get(first)?,
+        senddiff: row.get(first + 1)?,
+        skip_empty: row.get(first + 2)?,
+        diet: Diet::from_db(row.get(first + 3)?),
+        diet_mode: match row.get(first + 4)? {
+            true => DietMode::Dim,
+            false => DietMode::Hide,
+        },
+        avoid_allergens: row.get(first + 5)?,
+        allergen_mode: match row.get(first + 6)? {
+            true => AllergenMode::Hide,
+            false => AllergenMode::Warn,
+        },
+        price_tier: PriceTier::from_db(row.get(first + 7)?),
+        lang: Lang::from_db(&row.get::<_, String>(first + 8)?),
+    })
+}
 
-    let mut stmt = conn.prepare_cached(
-        "select allergens from registrations
-        where chat_id = ?1",
-    )?;
+pub async fn get_user_settings(chat_id: i64) -> rusqlite::Result<UserSettings> {
+    run_db(move |conn| {
+        let mut stmt = conn.prepare_cached(&format!(
+            "select {} from registrations where chat_id = ?1",
+            SETTINGS_COLUMNS
+        ))?;
 
-    let allergens: bool = stmt.query_row(params![chat_id], |row| row.get(0))?;
+        stmt.query_row(params![chat_id], |row| user_settings_from_row(row, 0))
+    })
+    .await
+}
 
-    Ok(allergens)
+/// Writes all settings in one statement
+pub async fn save_user_settings(chat_id: i64, settings: &UserSettings) -> rusqlite::Result<()> {
+    let settings = settings.clone();
+
+    run_db(move |conn| {
+        let mut stmt = conn.prepare_cached(
+            "update registrations
+            set allergens = ?2, senddiff = ?3, skip_empty = ?4, diet = ?5, diet_dim = ?6,
+                avoid_allergens = ?7, avoid_hide = ?8, price_tier = ?9, lang = ?10
+            where chat_id = ?1",
+        )?;
+
+        stmt.execute(params![
+            chat_id,
+            settings.allergens,
+            settings.senddiff,
+            settings.skip_empty,
+            settings.diet.to_db(),
+            settings.diet_mode == DietMode::Dim,
+            settings.avoid_allergens,
+            settings.allergen_mode == AllergenMode::Hide,
+            settings.price_tier.to_db(),
+            settings.lang.code()
+        ])?;
+
+        Ok(())
+    })
+    .await
 }
 
-pub fn set_user_allergen_state(chat_id: i64, state: bool) -> rusqlite::Result<()> {
-    let conn = Connection::open(DB_FILENAME.get().unwrap()).unwrap();
+pub async fn get_all_watches() -> rusqlite::Result<Vec<(i64, String)>> {
+    run_db(move |conn| {
+        let mut stmt = conn.prepare_cached("select chat_id, keyword from watches")?;
 
-    let mut stmt =
-        conn.prepare_cached("update registrations set allergens = ?2 where chat_id = ?1")?;
+        let watches = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<Vec<(i64, String)>>>()?;
 
-    stmt.execute(params![chat_id, state])?;
+        Ok(watches)
+    })
+    .await
+}
 
-    Ok(())
+pub async fn get_user_watches(chat_id: i64) -> rusqlite::Result<Vec<String>> {
+    run_db(move |conn| {
+        let mut stmt = conn.prepare_cached(
+            "select keyword from watches
+            where chat_id = ?1
+            order by keyword",
+        )?;
+
+        let keywords = stmt
+            .query_map(params![chat_id], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+
+        Ok(keywords)
+    })
+    .await
 }
 
-pub fn get_user_senddiff_state(chat_id: i64) -> rusqlite::Result<bool> {
-    let conn = Connection::open(DB_FILENAME.get().unwrap()).unwrap();
+/// Watches match case insensitively, so keywords are stored trimmed and lowercased
+fn normalize_watch_keyword(keyword: &str) -> String {
+    keyword.trim().to_lowercase()
+}
 
-    let mut stmt = conn.prepare_cached(
-        "select senddiff from registrations
-        where chat_id = ?1",
-    )?;
+/// Returns false if the keyword was already watched
+pub async fn add_watch(chat_id: i64, keyword: &str) -> rusqlite::Result<bool> {
+    let keyword = normalize_watch_keyword(keyword);
 
-    let diff: bool = stmt.query_row(params![chat_id], |row| row.get(0))?;
+    run_db(move |conn| {
+        let mut stmt = conn
+            .prepare_cached("insert or ignore into watches (chat_id, keyword) values (?1, ?2)")?;
 
-    Ok(diff)
+        Ok(stmt.execute(params![chat_id, keyword])? > 0)
+    })
+    .await
 }
 
-pub fn set_user_senddiff_state(chat_id: i64, state: bool) -> rusqlite::Result<()> {
-    let conn = Connection::open(DB_FILENAME.get().unwrap()).unwrap();
+/// Returns false if the keyword wasn't watched
+pub async fn remove_watch(chat_id: i64, keyword: &str) -> rusqlite::Result<bool> {
+    let keyword = normalize_watch_keyword(keyword);
 
-    let mut stmt =
-        conn.prepare_cached("update registrations set senddiff = ?2 where chat_id = ?1")?;
+    run_db(move |conn| {
+        let mut stmt =
+            conn.prepare_cached("delete from watches where chat_id = ?1 and keyword = ?2")?;
 
-    stmt.execute(params![chat_id, state])?;
+        Ok(stmt.execute(params![chat_id, keyword])? > 0)
+    })
+    .await
+}
 
-    Ok(())
+pub async fn was_watch_notified(
+    chat_id: i64,
+    date: NaiveDate,
+    meal_name: &str,
+) -> rusqlite::Result<bool> {
+    let meal_name = meal_name.to_string();
+
+    run_db(move |conn| {
+        let mut stmt = conn.prepare_cached(
+            "select 1 from watch_notifications
+            where chat_id = ?1 and date = ?2 and meal_name = ?3",
+        )?;
+
+        stmt.exists(params![chat_id, date_str(date), meal_name])
+    })
+    .await
+}
+
+pub async fn save_watch_notified(
+    chat_id: i64,
+    date: NaiveDate,
+    meal_name: &str,
+) -> rusqlite::Result<()> {
+    let meal_name = meal_name.to_string();
+
+    run_db(move |conn| {
+        let mut stmt = conn.prepare_cached(
+            "insert or ignore into watch_notifications (chat_id, date, meal_name)
+                values (?1, ?2, ?3)",
+        )?;
+        stmt.execute(params![chat_id, date_str(date), meal_name])?;
+
+        // past days can't be notified again anyway
+        let mut prune_stmt =
+            conn.prepare_cached("delete from watch_notifications where date < ?1")?;
+        prune_stmt.execute(params![date_str(Local::now().date_naive())])?;
+
+        Ok(())
+    })
+    .await
+}
+
+/// Dialogue state of a chat, `None` if there is none or it is older than `timeout`
+pub async fn get_dialogue_state(
+    chat_id: i64,
+    timeout: Duration,
+) -> rusqlite::Result<Option<DialogueState>> {
+    run_db(move |conn| {
+        let mut stmt = conn.prepare_cached(
+            "select state from dialogue_states
+            where chat_id = ?1 and updated_at >= ?2",
+        )?;
+
+        let state: Option<String> = stmt
+            .query_row(
+                params![chat_id, (Local::now() - timeout).timestamp()],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        // states of an older version that can't be read anymore are reset
+        Ok(state.and_then(|json| serde_json::from_str(&json).ok()))
+    })
+    .await
+}
+
+pub async fn save_dialogue_state(
+    chat_id: i64,
+    state: &DialogueState,
+    timeout: Duration,
+) -> rusqlite::Result<()> {
+    let state = serde_json::to_string(state).unwrap();
+
+    run_db(move |conn| {
+        let mut stmt = conn.prepare_cached(
+            "replace into dialogue_states (chat_id, state, updated_at) values (?1, ?2, ?3)",
+        )?;
+        stmt.execute(params![chat_id, state, Local::now().timestamp()])?;
+
+        // expired dialogues of other chats
+        let mut prune_stmt =
+            conn.prepare_cached("delete from dialogue_states where updated_at < ?1")?;
+        prune_stmt.execute(params![(Local::now() - timeout).timestamp()])?;
+
+        Ok(())
+    })
+    .await
+}
+
+pub async fn delete_dialogue_state(chat_id: i64) -> rusqlite::Result<()> {
+    run_db(move |conn| {
+        let mut stmt = conn.prepare_cached("delete from dialogue_states where chat_id = ?1")?;
+        stmt.execute(params![chat_id])?;
+
+        Ok(())
+    })
+    .await
+}
+
+pub async fn get_cached_meals(
+    backend: &str,
+    canteen_id: u32,
+    date: NaiveDate,
+) -> rusqlite::Result<Option<(DateTime<Local>, Vec<MealGroup>)>> {
+    let backend = backend.to_string();
+
+    run_db(move |conn| {
+        let mut stmt = conn.prepare_cached(
+            "select fetched_at, meal_groups from meal_cache
+            where backend = ?1 and canteen_id = ?2 and date = ?3",
+        )?;
+
+        let cached: Option<(i64, String)> = stmt
+            .query_row(params![backend, canteen_id, date_str(date)], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .optional()?;
+
+        // rows that can't be read (e.g. older meal format) are treated as missing
+        Ok(cached.and_then(|(fetched_at, json)| {
+            Some((
+                Local.timestamp_opt(fetched_at, 0).single()?,
+                serde_json::from_str(&json).ok()?,
+            ))
+        }))
+    })
+    .await
+}
+
+pub async fn save_cached_meals(
+    backend: &str,
+    canteen_id: u32,
+    date: NaiveDate,
+    meal_groups: &[MealGroup],
+) -> rusqlite::Result<()> {
+    let backend = backend.to_string();
+    let meal_groups = serde_json::to_string(meal_groups).unwrap();
+
+    run_db(move |conn| {
+        let mut stmt = conn.prepare_cached(
+            "replace into meal_cache (backend, canteen_id, date, fetched_at, meal_groups)
+                values (?1, ?2, ?3, ?4, ?5)",
+        )?;
+        stmt.execute(params![
+            backend,
+            canteen_id,
+            date_str(date),
+            Local::now().timestamp(),
+            meal_groups
+        ])?;
+
+        // past plans are of no use anymore
+        let mut prune_stmt = conn.prepare_cached("delete from meal_cache where date < ?1")?;
+        prune_stmt.execute(params![date_str(
+            Local::now().date_naive() - Duration::days(7)
+        )])?;
+
+        Ok(())
+    })
+    .await
+}
+
+/// Expires the cached plan, it stays available as fallback if the backend fails
+pub async fn invalidate_cached_meals(
+    backend: &str,
+    canteen_id: u32,
+    date: NaiveDate,
+) -> rusqlite::Result<()> {
+    let backend = backend.to_string();
+
+    run_db(move |conn| {
+        let mut stmt = conn.prepare_cached(
+            "update meal_cache set fetched_at = 0
+            where backend = ?1 and canteen_id = ?2 and date = ?3",
+        )?;
+        stmt.execute(params![backend, canteen_id, date_str(date)])?;
+
+        Ok(())
+    })
+    .await
+}
+
+fn date_str(date: NaiveDate) -> String {
+    date.format("%Y-%m-%d").to_string()
 }
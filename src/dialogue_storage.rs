@@ -0,0 +1,48 @@
+use std::{future::Future, pin::Pin, sync::Arc};
+
+use chrono::Duration;
+use teloxide::{dispatching::dialogue::Storage, types::ChatId};
+
+use crate::{
+    constants::DIALOGUE_TIMEOUT_MINS,
+    data_types::DialogueState,
+    db_operations::{delete_dialogue_state, get_dialogue_state, save_dialogue_state},
+};
+
+type StorageFuture<T> = Pin<Box<dyn Future<Output = rusqlite::Result<T>> + Send>>;
+
+/// Dialogue states in the bot's database, so open dialogues survive a restart.
+///
+/// Dialogues untouched for `DIALOGUE_TIMEOUT_MINS` are reset to the default state.
+#[derive(Debug, Default)]
+pub struct SqliteDialogueStorage;
+
+impl SqliteDialogueStorage {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self)
+    }
+}
+
+fn timeout() -> Duration {
+    Duration::minutes(DIALOGUE_TIMEOUT_MINS)
+}
+
+impl Storage<DialogueState> for SqliteDialogueStorage {
+    type Error = rusqlite::Error;
+
+    fn remove_dialogue(self: Arc<Self>, chat_id: ChatId) -> StorageFuture<()> {
+        Box::pin(delete_dialogue_state(chat_id.0))
+    }
+
+    fn update_dialogue(
+        self: Arc<Self>,
+        chat_id: ChatId,
+        dialogue: DialogueState,
+    ) -> StorageFuture<()> {
+        Box::pin(async move { save_dialogue_state(chat_id.0, &dialogue, timeout()).await })
+    }
+
+    fn get_dialogue(self: Arc<Self>, chat_id: ChatId) -> StorageFuture<Option<DialogueState>> {
+        Box::pin(get_dialogue_state(chat_id.0, timeout()))
+    }
+}
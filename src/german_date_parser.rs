@@ -0,0 +1,210 @@
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use regex_lite::Regex;
+use static_init::dynamic;
+
+/// Resolves a German (or ISO) date description relative to `today`.
+///
+/// Understands e.g. "heute", "übermorgen", "24.10.", "24.10.2024", "2024-10-24",
+/// "Donnerstag", "nächsten Montag", "übernächsten Fr", "in 3 Tagen", "in 2 Wochen",
+/// and the English "tomorrow", "thursday", "next monday", "in 3 days", "in a week".
+pub fn parse_german_date(input: &str, today: NaiveDate) -> Option<NaiveDate> {
+    #[dynamic]
+    static ISO_RE: Regex = Regex::new(r"^(\d{4})-(\d{1,2})-(\d{1,2})$").unwrap();
+    #[dynamic]
+    static DMY_RE: Regex = Regex::new(r"^(\d{1,2})\.(\d{1,2})\.?(\d{2}|\d{4})?$").unwrap();
+    #[dynamic]
+    static IN_RE: Regex = Regex::new(concat!(
+        r"^in (\d+|einem|einer|zwei|drei|a|an|one|two|three) ",
+        r"(tag|tagen|woche|wochen|day|days|week|weeks)$"
+    ))
+    .unwrap();
+
+    let input = input.trim().to_lowercase();
+    let input = input.split_whitespace().collect::<Vec<&str>>().join(" ");
+
+    match input.as_str() {
+        "heute" | "today" => return Some(today),
+        "morgen" | "tomorrow" => return Some(today + Duration::days(1)),
+        "übermorgen" | "uebermorgen" | "day after tomorrow" => {
+            return Some(today + Duration::days(2))
+        }
+        _ => {}
+    }
+
+    if let Some(caps) = ISO_RE.captures(&input) {
+        return NaiveDate::from_ymd_opt(
+            caps[1].parse().ok()?,
+            caps[2].parse().ok()?,
+            caps[3].parse().ok()?,
+        );
+    }
+
+    if let Some(caps) = DMY_RE.captures(&input) {
+        let day = caps[1].parse().ok()?;
+        let month = caps[2].parse().ok()?;
+
+        return match caps.get(3) {
+            Some(year) => {
+                let mut year: i32 = year.as_str().parse().ok()?;
+                if year < 100 {
+                    year += 2000;
+                }
+                NaiveDate::from_ymd_opt(year, month, day)
+            }
+            None => {
+                // without a year, dates long past (e.g. "05.01." in december) mean next year
+                let date = NaiveDate::from_ymd_opt(today.year(), month, day)?;
+                if date < today - Duration::days(90) {
+                    NaiveDate::from_ymd_opt(today.year() + 1, month, day)
+                } else {
+                    Some(date)
+                }
+            }
+        };
+    }
+
+    if let Some(caps) = IN_RE.captures(&input) {
+        let count: i64 = match &caps[1] {
+            "einem" | "einer" | "a" | "an" | "one" => 1,
+            "zwei" | "two" => 2,
+            "drei" | "three" => 3,
+            n => n.parse().ok()?,
+        };
+        let days = if caps[2].starts_with("woche") || caps[2].starts_with("week") {
+            count.checked_mul(7)?
+        } else {
+            count
+        };
+        return today.checked_add_signed(Duration::try_days(days)?);
+    }
+
+    // weekdays: "freitag" (today or later), "nächsten freitag" (after today), "übernächsten freitag"
+    let (weeks_skipped, weekday_str) = match input.split_once(' ') {
+        Some((prefix, weekday_str)) => match prefix {
+            "nächsten" | "nächster" | "naechsten" | "naechster" | "kommenden" | "kommender"
+            | "next" => (Some(0), weekday_str),
+            "übernächsten" | "übernächster" | "uebernaechsten" | "uebernaechster" => {
+                (Some(1), weekday_str)
+            }
+            _ => return None,
+        },
+        None => (None, input.as_str()),
+    };
+
+    // English names and abbreviations ("thu") via chrono
+    let weekday = parse_german_weekday(weekday_str).or_else(|| weekday_str.parse().ok())?;
+    let days_until = i64::from(
+        (weekday.num_days_from_monday() + 7 - today.weekday().num_days_from_monday()) % 7,
+    );
+
+    match weeks_skipped {
+        None => Some(today + Duration::days(days_until)),
+        Some(weeks) => {
+            // "nächsten <heute>" is next week's day, not today
+            let days_until = if days_until == 0 { 7 } else { days_until };
+            Some(today + Duration::days(days_until + 7 * weeks))
+        }
+    }
+}
+
+/// Weekday from its German name or an abbreviation of at least two letters
+pub fn parse_german_weekday(input: &str) -> Option<Weekday> {
+    let input = input.trim_end_matches('.');
+    let weekdays = [
+        (Weekday::Mon, "montag"),
+        (Weekday::Tue, "dienstag"),
+        (Weekday::Wed, "mittwoch"),
+        (Weekday::Thu, "donnerstag"),
+        (Weekday::Fri, "freitag"),
+        (Weekday::Sat, "samstag"),
+        (Weekday::Sun, "sonntag"),
+    ];
+
+    weekdays
+        .iter()
+        // full name or abbreviation ("mo", "mitt")
+        .find(|(_, name)| input.len() >= 2 && name.starts_with(input))
+        .map(|(weekday, _)| *weekday)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    // a wednesday
+    fn today() -> NaiveDate {
+        date(2025, 10, 22)
+    }
+
+    fn parse(input: &str) -> Option<NaiveDate> {
+        parse_german_date(input, today())
+    }
+
+    #[test]
+    fn keywords() {
+        assert_eq!(parse("heute"), Some(today()));
+        assert_eq!(parse(" Morgen "), Some(date(2025, 10, 23)));
+        assert_eq!(parse("übermorgen"), Some(date(2025, 10, 24)));
+    }
+
+    #[test]
+    fn dates() {
+        assert_eq!(parse("24.10."), Some(date(2025, 10, 24)));
+        assert_eq!(parse("24.10"), Some(date(2025, 10, 24)));
+        assert_eq!(parse("24.10.2024"), Some(date(2024, 10, 24)));
+        assert_eq!(parse("24.10.26"), Some(date(2026, 10, 24)));
+        assert_eq!(parse("2025-10-24"), Some(date(2025, 10, 24)));
+        assert_eq!(parse("2025-1-5"), Some(date(2025, 1, 5)));
+        assert_eq!(parse("31.02."), None);
+        assert_eq!(parse("2025-13-01"), None);
+    }
+
+    #[test]
+    fn dates_without_year_roll_over_after_90_days() {
+        // 90 days before today is 24.07.
+        assert_eq!(parse("24.07."), Some(date(2025, 7, 24)));
+        assert_eq!(parse("23.07."), Some(date(2026, 7, 23)));
+        assert_eq!(
+            parse_german_date("05.01.", date(2025, 12, 20)),
+            Some(date(2026, 1, 5))
+        );
+    }
+
+    #[test]
+    fn weekdays() {
+        assert_eq!(parse("Donnerstag"), Some(date(2025, 10, 23)));
+        assert_eq!(parse("Mittwoch"), Some(today()));
+        assert_eq!(parse("di."), Some(date(2025, 10, 28)));
+        assert_eq!(parse("nächsten Montag"), Some(date(2025, 10, 27)));
+        assert_eq!(parse("nächsten Mittwoch"), Some(date(2025, 10, 29)));
+        assert_eq!(parse("übernächsten Fr"), Some(date(2025, 10, 31)));
+        assert_eq!(parse("letzten Montag"), None);
+        assert_eq!(parse("m"), None);
+    }
+
+    #[test]
+    fn relative_days() {
+        assert_eq!(parse("in 3 Tagen"), Some(date(2025, 10, 25)));
+        assert_eq!(parse("in einem Tag"), Some(date(2025, 10, 23)));
+        assert_eq!(parse("in einer Woche"), Some(date(2025, 10, 29)));
+        assert_eq!(parse("in 2 Wochen"), Some(date(2025, 11, 5)));
+        assert_eq!(parse("in 3"), None);
+    }
+
+    #[test]
+    fn english() {
+        assert_eq!(parse("today"), Some(today()));
+        assert_eq!(parse("Tomorrow"), Some(date(2025, 10, 23)));
+        assert_eq!(parse("day after tomorrow"), Some(date(2025, 10, 24)));
+        assert_eq!(parse("thursday"), Some(date(2025, 10, 23)));
+        assert_eq!(parse("thu"), Some(date(2025, 10, 23)));
+        assert_eq!(parse("next monday"), Some(date(2025, 10, 27)));
+        assert_eq!(parse("in 3 days"), Some(date(2025, 10, 25)));
+        assert_eq!(parse("in a week"), Some(date(2025, 10, 29)));
+        assert_eq!(parse("in 2 weeks"), Some(date(2025, 11, 5)));
+    }
+}
@@ -0,0 +1,205 @@
+use super::Texts;
+
+pub(super) const TEXTS: Texts = Texts {
+    commands: &[
+        ("heute", "Gerichte für heute"),
+        ("morgen", "Gerichte für morgen\n"),
+        ("woche", "Wochenplan"),
+        ("tag", "Gerichte an einem Datum (z.B. /tag Freitag)"),
+        ("andere", "Andere Mensa anzeigen"),
+        ("mensa", "Mensa wechseln\n"),
+        ("einstellungen", "Alle Einstellungen anzeigen und ändern"),
+        ("mensen", "Weiteren Mensen folgen"),
+        ("subscribe", "autom. Nachrichten aktivieren"),
+        ("unsubscribe", "autom. Nachrichten deaktivieren"),
+        (
+            "uhrzeit",
+            "Sendezeitpunkt ändern (auch je Tag: /uhrzeit Mo 07:00)",
+        ),
+        ("wochentage", "Wochentage für autom. Nachrichten"),
+        ("diff", "Bei Planänderungen nur Unterschied schicken"),
+        ("auslassen", "Keine autom. Nachricht an Tagen ohne Gerichte"),
+        ("ernaehrung", "Ernährungsweise (vegan, vegetarisch, ...)"),
+        ("allergieprofil", "Allergene, vor denen gewarnt wird"),
+        (
+            "preise",
+            "Angezeigte Preisstufe (Studierende, Bedienstete, Gäste)",
+        ),
+        (
+            "merken",
+            "Bei einem Gericht benachrichtigen (z.B. /merken Schnitzel)",
+        ),
+        ("vergessen", "Gemerktes Gericht vergessen"),
+        ("sprache", "Sprache / Language"),
+        ("abbrechen", "Laufende Eingabe abbrechen"),
+    ],
+    command_keyrow: [
+        &["/heute", "/morgen", "/woche", "/andere"],
+        &["/mensa", "/allergene", "/diff"],
+    ],
+
+    no_db: "Bitte zuerst /start ausführen",
+    admin_only: "Nur Gruppenadmins können die Einstellungen ändern.",
+    not_a_command: "Das ist kein Befehl.",
+    not_text: "Das ist kein Text.\nBitte mit Uhrzeit antworten:",
+    error_occurred: "Ein Fehler ist aufgetreten.",
+    choose_mensa: "Mensa auswählen:",
+    selected_mensa: |mensa| format!("Gewählte Mensa: {}", mensa),
+    registered: |mensa| {
+        format!(
+            "Plan der {} wird ab jetzt automatisch an Wochentagen *06:00 Uhr* gesendet\\.\n\n\
+            Ändern mit\n/mensa oder /uhrzeit",
+            mensa
+        )
+    },
+    date_not_recognized: "Datum nicht erkannt. Beispiele:\n/tag 24.10.\n/tag Donnerstag\n/tag nächsten Montag\n/tag in 3 Tagen",
+
+    auto_already_on: "Automatische Nachrichten sind schon aktiviert.",
+    auto_now_on: "Plan wird ab jetzt automatisch an Wochentagen *06:00 Uhr* gesendet\\.\n\nÄndern mit /uhrzeit",
+    auto_already_off: "Automatische Nachrichten waren bereits deaktiviert.",
+    auto_now_off: "Plan wird nicht mehr automatisch gesendet.",
+    auto_time_set: |hour, minute| {
+        format!(
+            "Plan wird ab jetzt automatisch an Wochentagen {:02}:{:02} Uhr gesendet.\n\n\
+            /unsubscribe zum Deaktivieren",
+            hour, minute
+        )
+    },
+    reply_with_time: "Bitte mit Uhrzeit antworten:",
+    oracle_asking: "Das Orakel wird befragt...",
+    oracle_uncertain: "Das Orakel verharrt in Ungewissheit.\nBitte erneut:",
+    oracle_unavailable: "Das Orakel erhört unsere Bitten nicht.\nBitte im Format HH:mm antworten:",
+    time_invalid: "Eingegebene Zeit ist ungültig.\nBitte mit Uhrzeit antworten:",
+    weekday_time_invalid: "Zeit konnte nicht gelesen werden.\nBeispiel: /uhrzeit Mo 07:00\nZurücksetzen: /uhrzeit Mo standard",
+    schedule_off: "Automatische Nachrichten sind deaktiviert.\n/subscribe zum Aktivieren",
+    schedule_no_days: "Kein Wochentag ausgewählt, es wird nichts gesendet.",
+    schedule_header: "Plan wird gesendet:",
+    weekday_abbr: ["Mo", "Di", "Mi", "Do", "Fr", "Sa", "So"],
+
+    allergens_on: "✅ Allergene werden jetzt angezeigt.",
+    allergens_off: "❌ Allergene werden nicht mehr angezeigt.",
+    diff_on: "✅ Nur Änderungen werden bei Planänderung gesendet.",
+    diff_off: "❌ Gesamter Plan wird bei Änderungen gesendet.",
+    skip_empty_on: "✅ An Tagen ohne Gerichte wird nichts automatisch gesendet.",
+    skip_empty_off: "❌ Plan wird auch an Tagen ohne Gerichte gesendet.",
+
+    choose_follow: "Weitere Mensen auswählen, die mit im Plan stehen sollen:",
+    follow_page: "Weiteren Mensen folgen:",
+    choose_diet: "Ernährungsweise auswählen:",
+    diet_others_hidden: "Andere Gerichte: ausgeblendet",
+    diet_others_dimmed: "Andere Gerichte: durchgestrichen",
+    diet_labels: [
+        "Alles",
+        "Vegetarisch",
+        "Vegan",
+        "Kein Fisch",
+        "Kein Schwein",
+    ],
+    choose_allergens: "Allergene auswählen, vor denen gewarnt werden soll:",
+    allergens_marked: "Betroffene Gerichte: markiert ⚠️",
+    allergens_hidden: "Betroffene Gerichte: ausgeblendet",
+    allergen_names: [
+        "Gluten",
+        "Krebstiere",
+        "Eier",
+        "Fisch",
+        "Erdnüsse",
+        "Soja",
+        "Milch",
+        "Schalenfrüchte",
+        "Sellerie",
+        "Senf",
+        "Sesam",
+        "Sulfite",
+        "Lupinen",
+        "Weichtiere",
+    ],
+    no_price_tiers: "Diese Datenquelle hat keine Preisstufen.",
+    choose_price_tier: "Angezeigte Preise:",
+    price_tier_labels: ["Alle Preise", "Studierende", "Bedienstete", "Gäste"],
+    choose_lang: "Sprache auswählen:",
+    week_prev: "◀ Vorwoche",
+    week_next: "Nächste Woche ▶",
+
+    settings_title: "⚙️ Einstellungen",
+    settings_rows: [
+        "Mensa",
+        "Weitere Mensen",
+        "Autom. Nachrichten",
+        "Allergene anzeigen",
+        "Bei Planänderung nur Unterschied",
+        "Tage ohne Gerichte auslassen",
+        "Ernährung",
+        "Allergieprofil",
+        "Preise",
+        "Sprache",
+    ],
+    on: "an",
+    off: "aus",
+    none: "keine",
+    no_weekday_selected: "kein Wochentag ausgewählt",
+    others_hidden: "andere ausgeblendet",
+    others_dimmed: "andere durchgestrichen",
+    marked: "markiert",
+    hidden: "ausgeblendet",
+    btn_mensa: "🏫 Mensa",
+    btn_follow: "➕ Weitere Mensen",
+    btn_auto: "Autom. Nachrichten",
+    btn_time: "🕖 Uhrzeit",
+    btn_weekdays: "📅 Wochentage",
+    btn_allergens: "Allergene",
+    btn_diff: "Nur Unterschied",
+    btn_skip_empty: "Tage ohne Gerichte auslassen",
+    btn_diet: "🥦 Ernährung",
+    btn_avoid: "⚠️ Allergieprofil",
+    btn_price: "💶 Preise",
+    btn_lang: "🌐 Sprache",
+    btn_back: "« Zurück",
+
+    watch_none_yet: "Noch keine Gerichte gemerkt. Beispiel:\n/merken Schnitzel",
+    watch_list: "Gemerkte Gerichte:",
+    watch_too_long: |max_len| format!("Maximal {} Zeichen.", max_len),
+    watch_added: |keyword| {
+        format!(
+            "Gemerkt: {}\nBenachrichtigung folgt, sobald es angeboten wird.",
+            keyword
+        )
+    },
+    watch_exists: |keyword| format!("{} ist bereits gemerkt.", keyword),
+    watch_none: "Keine Gerichte gemerkt.",
+    watch_which: "Welches Gericht vergessen?",
+    unwatched: |keyword| format!("{} vergessen.", keyword),
+    not_watched: |keyword| format!("{} war nicht gemerkt.", keyword),
+    watch_none_left: "Keine Gerichte mehr gemerkt.",
+    watch_found: "🔔 Gemerkte Gerichte gefunden:",
+
+    cancelled: "Abgebrochen.",
+    nothing_to_cancel: "Nichts abzubrechen.",
+
+    weekdays: [
+        "Montag",
+        "Dienstag",
+        "Mittwoch",
+        "Donnerstag",
+        "Freitag",
+        "Samstag",
+        "Sonntag",
+    ],
+    date_format: "%d.%m.%Y",
+    tomorrow_note: "(Morgen)",
+    day_after_note: "(Übermorgen)",
+    update_failed_since: "⚠️ Aktualisierung fehlgeschlagen, Stand:",
+    no_data: "keine Daten vorhanden.",
+    no_matching_meals: "keine passenden Gerichte",
+    allergen_profile: "Allergieprofil",
+    rating: "Bewertung",
+    variations: "Variationen:",
+
+    plan_changed: "Planänderung",
+    new_meals: ["Neues Gericht:", "Neue Gerichte:"],
+    modified_meals: ["Geändertes Gericht:", "Geänderte Gerichte:"],
+    removed_meals: ["Entferntes Gericht:", "Entfernte Gerichte:"],
+
+    new_grade: "Neue Note:",
+    new_signup_option: "Neue Anmeldemöglichkeit:",
+};
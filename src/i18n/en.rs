@@ -0,0 +1,192 @@
+use super::Texts;
+
+pub(super) const TEXTS: Texts = Texts {
+    commands: &[
+        ("today", "Meals of today"),
+        ("tomorrow", "Meals of tomorrow\n"),
+        ("week", "Weekly plan"),
+        ("day", "Meals at a date (e.g. /day 24.10.)"),
+        ("other", "Show another canteen"),
+        ("canteen", "Change canteen\n"),
+        ("settings", "Show and change all settings"),
+        ("follow", "Follow more canteens"),
+        ("subscribe", "Enable automatic messages"),
+        ("unsubscribe", "Disable automatic messages"),
+        ("time", "Change send time (also per day: /time Mon 07:00)"),
+        ("weekdays", "Weekdays of automatic messages"),
+        ("diff", "Only send the difference on plan changes"),
+        ("skipempty", "No automatic message on days without meals"),
+        ("diet", "Diet (vegan, vegetarian, ...)"),
+        ("allergies", "Allergens to be warned about"),
+        ("prices", "Shown price tier (students, staff, guests)"),
+        ("watch", "Get notified about a meal (e.g. /watch schnitzel)"),
+        ("unwatch", "Forget a watched meal"),
+        ("language", "Sprache / Language"),
+        ("cancel", "Cancel the current input"),
+    ],
+    command_keyrow: [
+        &["/today", "/tomorrow", "/week", "/other"],
+        &["/canteen", "/allergens", "/diff"],
+    ],
+
+    no_db: "Please run /start first",
+    admin_only: "Only group admins can change the settings.",
+    not_a_command: "That is not a command.",
+    not_text: "That is not text.\nPlease reply with a time:",
+    error_occurred: "An error occurred.",
+    choose_mensa: "Choose a canteen:",
+    selected_mensa: |mensa| format!("Selected canteen: {}", mensa),
+    registered: |mensa| {
+        format!(
+            "The plan of {} will now be sent automatically on weekdays at *06:00*\\.\n\n\
+            Change with\n/canteen or /time",
+            mensa
+        )
+    },
+    date_not_recognized: "Date not recognised. Examples:\n/day tomorrow\n/day thursday\n/day next monday\n/day in 3 days\n/day 2025-10-24",
+
+    auto_already_on: "Automatic messages are already enabled.",
+    auto_now_on:
+        "The plan will now be sent automatically on weekdays at *06:00*\\.\n\nChange with /time",
+    auto_already_off: "Automatic messages were already disabled.",
+    auto_now_off: "The plan is no longer sent automatically.",
+    auto_time_set: |hour, minute| {
+        format!(
+            "The plan will now be sent automatically on weekdays at {:02}:{:02}.\n\n\
+            /unsubscribe to disable",
+            hour, minute
+        )
+    },
+    reply_with_time: "Please reply with a time:",
+    oracle_asking: "Consulting the oracle...",
+    oracle_uncertain: "The oracle remains uncertain.\nPlease try again:",
+    oracle_unavailable: "The oracle does not hear our pleas.\nPlease reply in the format HH:mm:",
+    time_invalid: "The time is invalid.\nPlease reply with a time:",
+    weekday_time_invalid:
+        "Could not read the time.\nExample: /time Mon 07:00\nReset: /time Mon default",
+    schedule_off: "Automatic messages are disabled.\n/subscribe to enable",
+    schedule_no_days: "No weekday selected, nothing will be sent.",
+    schedule_header: "The plan is sent:",
+    weekday_abbr: ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"],
+
+    allergens_on: "✅ Allergens are shown now.",
+    allergens_off: "❌ Allergens are no longer shown.",
+    diff_on: "✅ Only the difference is sent on plan changes.",
+    diff_off: "❌ The whole plan is sent on changes.",
+    skip_empty_on: "✅ Nothing is sent automatically on days without meals.",
+    skip_empty_off: "❌ The plan is also sent on days without meals.",
+
+    choose_follow: "Choose more canteens to include in the plan:",
+    follow_page: "Follow more canteens:",
+    choose_diet: "Choose a diet:",
+    diet_others_hidden: "Other meals: hidden",
+    diet_others_dimmed: "Other meals: struck through",
+    diet_labels: ["Everything", "Vegetarian", "Vegan", "No fish", "No pork"],
+    choose_allergens: "Choose the allergens to be warned about:",
+    allergens_marked: "Affected meals: marked ⚠️",
+    allergens_hidden: "Affected meals: hidden",
+    allergen_names: [
+        "Gluten",
+        "Crustaceans",
+        "Eggs",
+        "Fish",
+        "Peanuts",
+        "Soy",
+        "Milk",
+        "Tree nuts",
+        "Celery",
+        "Mustard",
+        "Sesame",
+        "Sulphites",
+        "Lupin",
+        "Molluscs",
+    ],
+    no_price_tiers: "This data source has no price tiers.",
+    choose_price_tier: "Shown prices:",
+    price_tier_labels: ["All prices", "Students", "Staff", "Guests"],
+    choose_lang: "Choose a language:",
+    week_prev: "◀ Previous week",
+    week_next: "Next week ▶",
+
+    settings_title: "⚙️ Settings",
+    settings_rows: [
+        "Canteen",
+        "More canteens",
+        "Automatic messages",
+        "Show allergens",
+        "Only difference on plan changes",
+        "Skip days without meals",
+        "Diet",
+        "Allergen profile",
+        "Prices",
+        "Language",
+    ],
+    on: "on",
+    off: "off",
+    none: "none",
+    no_weekday_selected: "no weekday selected",
+    others_hidden: "others hidden",
+    others_dimmed: "others struck through",
+    marked: "marked",
+    hidden: "hidden",
+    btn_mensa: "🏫 Canteen",
+    btn_follow: "➕ More canteens",
+    btn_auto: "Automatic messages",
+    btn_time: "🕖 Time",
+    btn_weekdays: "📅 Weekdays",
+    btn_allergens: "Allergens",
+    btn_diff: "Only difference",
+    btn_skip_empty: "Skip days without meals",
+    btn_diet: "🥦 Diet",
+    btn_avoid: "⚠️ Allergen profile",
+    btn_price: "💶 Prices",
+    btn_lang: "🌐 Language",
+    btn_back: "« Back",
+
+    watch_none_yet: "No meals watched yet. Example:\n/watch schnitzel",
+    watch_list: "Watched meals:",
+    watch_too_long: |max_len| format!("At most {} characters.", max_len),
+    watch_added: |keyword| {
+        format!(
+            "Watching: {}\nYou will be notified once it is offered.",
+            keyword
+        )
+    },
+    watch_exists: |keyword| format!("{} is already watched.", keyword),
+    watch_none: "No meals watched.",
+    watch_which: "Which meal should be forgotten?",
+    unwatched: |keyword| format!("{} forgotten.", keyword),
+    not_watched: |keyword| format!("{} was not watched.", keyword),
+    watch_none_left: "No meals watched anymore.",
+    watch_found: "🔔 Watched meals found:",
+
+    cancelled: "Cancelled.",
+    nothing_to_cancel: "Nothing to cancel.",
+
+    weekdays: [
+        "Monday",
+        "Tuesday",
+        "Wednesday",
+        "Thursday",
+        "Friday",
+        "Saturday",
+        "Sunday",
+    ],
+    date_format: "%d %b %Y",
+    tomorrow_note: "(Tomorrow)",
+    day_after_note: "(Day after tomorrow)",
+    update_failed_since: "⚠️ Update failed, as of:",
+    no_data: "no data available.",
+    no_matching_meals: "no matching meals",
+    allergen_profile: "allergen profile",
+    rating: "Rating",
+    variations: "Variations:",
+
+    plan_changed: "Plan change",
+    new_meals: ["New meal:", "New meals:"],
+    modified_meals: ["Changed meal:", "Changed meals:"],
+    removed_meals: ["Removed meal:", "Removed meals:"],
+
+    new_grade: "New grade:",
+    new_signup_option: "New signup option:",
+};
@@ -0,0 +1,212 @@
+use chrono::{Datelike, NaiveDate};
+use teloxide::types::BotCommand;
+
+mod de;
+mod en;
+
+/// Language of a chat, stored by its code
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub enum Lang {
+    #[default]
+    De,
+    En,
+}
+
+impl Lang {
+    pub const ALL: [Lang; 2] = [Lang::De, Lang::En];
+
+    /// Telegram's `language_code` of a user, everything but German gets English
+    pub fn from_code(language_code: Option<&str>) -> Self {
+        match language_code {
+            Some(code) if !code.starts_with("de") => Lang::En,
+            _ => Lang::De,
+        }
+    }
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Lang::De => "de",
+            Lang::En => "en",
+        }
+    }
+
+    pub fn from_db(code: &str) -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|lang| lang.code() == code)
+            .unwrap_or_default()
+    }
+
+    /// Always in the language itself
+    pub fn label(self) -> &'static str {
+        match self {
+            Lang::De => "Deutsch",
+            Lang::En => "English",
+        }
+    }
+
+    pub fn texts(self) -> &'static Texts {
+        match self {
+            Lang::De => &de::TEXTS,
+            Lang::En => &en::TEXTS,
+        }
+    }
+}
+
+/// All user-facing strings of one language
+pub struct Texts {
+    /// (name, description) in menu order, names have to exist as command or alias.
+    /// A description ending with '\n' starts a new group in the help text.
+    pub commands: &'static [(&'static str, &'static str)],
+    pub command_keyrow: [&'static [&'static str]; 2],
+
+    pub no_db: &'static str,
+    pub admin_only: &'static str,
+    pub not_a_command: &'static str,
+    pub not_text: &'static str,
+    pub error_occurred: &'static str,
+    pub choose_mensa: &'static str,
+    pub selected_mensa: fn(&str) -> String,
+    /// MarkdownV2, the mensa name is already formatted
+    pub registered: fn(&str) -> String,
+    pub date_not_recognized: &'static str,
+
+    // auto send
+    pub auto_already_on: &'static str,
+    /// MarkdownV2
+    pub auto_now_on: &'static str,
+    pub auto_already_off: &'static str,
+    pub auto_now_off: &'static str,
+    pub auto_time_set: fn(u32, u32) -> String,
+    pub reply_with_time: &'static str,
+    pub oracle_asking: &'static str,
+    pub oracle_uncertain: &'static str,
+    pub oracle_unavailable: &'static str,
+    pub time_invalid: &'static str,
+    pub weekday_time_invalid: &'static str,
+    pub schedule_off: &'static str,
+    pub schedule_no_days: &'static str,
+    pub schedule_header: &'static str,
+    pub weekday_abbr: [&'static str; 7],
+
+    // toggles
+    pub allergens_on: &'static str,
+    pub allergens_off: &'static str,
+    pub diff_on: &'static str,
+    pub diff_off: &'static str,
+    pub skip_empty_on: &'static str,
+    pub skip_empty_off: &'static str,
+
+    // selections
+    pub choose_follow: &'static str,
+    pub follow_page: &'static str,
+    pub choose_diet: &'static str,
+    pub diet_others_hidden: &'static str,
+    pub diet_others_dimmed: &'static str,
+    pub diet_labels: [&'static str; 5],
+    pub choose_allergens: &'static str,
+    pub allergens_marked: &'static str,
+    pub allergens_hidden: &'static str,
+    pub allergen_names: [&'static str; 14],
+    pub no_price_tiers: &'static str,
+    pub choose_price_tier: &'static str,
+    pub price_tier_labels: [&'static str; 4],
+    pub choose_lang: &'static str,
+    pub week_prev: &'static str,
+    pub week_next: &'static str,
+
+    // /einstellungen
+    pub settings_title: &'static str,
+    /// mensa, additional mensen, auto send, allergens, diff, skip empty, diet, allergen profile, prices, language
+    pub settings_rows: [&'static str; 10],
+    pub on: &'static str,
+    pub off: &'static str,
+    pub none: &'static str,
+    pub no_weekday_selected: &'static str,
+    pub others_hidden: &'static str,
+    pub others_dimmed: &'static str,
+    pub marked: &'static str,
+    pub hidden: &'static str,
+    pub btn_mensa: &'static str,
+    pub btn_follow: &'static str,
+    pub btn_auto: &'static str,
+    pub btn_time: &'static str,
+    pub btn_weekdays: &'static str,
+    pub btn_allergens: &'static str,
+    pub btn_diff: &'static str,
+    pub btn_skip_empty: &'static str,
+    pub btn_diet: &'static str,
+    pub btn_avoid: &'static str,
+    pub btn_price: &'static str,
+    pub btn_lang: &'static str,
+    pub btn_back: &'static str,
+
+    // /merken
+    pub watch_none_yet: &'static str,
+    pub watch_list: &'static str,
+    pub watch_too_long: fn(usize) -> String,
+    pub watch_added: fn(&str) -> String,
+    pub watch_exists: fn(&str) -> String,
+    pub watch_none: &'static str,
+    pub watch_which: &'static str,
+    pub unwatched: fn(&str) -> String,
+    pub not_watched: fn(&str) -> String,
+    pub watch_none_left: &'static str,
+    pub watch_found: &'static str,
+
+    pub cancelled: &'static str,
+    pub nothing_to_cancel: &'static str,
+
+    // meal plans
+    pub weekdays: [&'static str; 7],
+    /// chrono format of the date after the weekday
+    pub date_format: &'static str,
+    pub tomorrow_note: &'static str,
+    pub day_after_note: &'static str,
+    pub update_failed_since: &'static str,
+    pub no_data: &'static str,
+    pub no_matching_meals: &'static str,
+    /// named after `no_matching_meals` if it hid every meal
+    pub allergen_profile: &'static str,
+    pub rating: &'static str,
+    pub variations: &'static str,
+
+    // plan changes, (one, several)
+    pub plan_changed: &'static str,
+    pub new_meals: [&'static str; 2],
+    pub modified_meals: [&'static str; 2],
+    pub removed_meals: [&'static str; 2],
+
+    // CampusDual
+    pub new_grade: &'static str,
+    pub new_signup_option: &'static str,
+}
+
+/// e.g. "Montag, 24.10.2025" or "Monday, 24 Oct 2025"
+pub fn date_fmt(date: NaiveDate, lang: Lang) -> String {
+    let texts = lang.texts();
+    format!(
+        "{}, {}",
+        texts.weekdays[date.weekday().num_days_from_monday() as usize],
+        date.format(texts.date_format)
+    )
+}
+
+/// Command menu as registered with `set_my_commands`
+pub fn bot_commands(lang: Lang) -> Vec<BotCommand> {
+    lang.texts()
+        .commands
+        .iter()
+        .map(|(name, description)| BotCommand::new(*name, description.trim_end()))
+        .collect()
+}
+
+/// List of commands shown after /start
+pub fn help_text(lang: Lang) -> String {
+    lang.texts()
+        .commands
+        .iter()
+        .map(|(name, description)| format!("/{} — {}", name, description))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
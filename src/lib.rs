@@ -1,9 +1,19 @@
+pub mod admin_commands;
+pub mod allergen_parser;
 pub mod bot_command_handlers;
 pub mod bot_command_helpers;
 pub mod campusdual_fetcher;
 pub mod constants;
 pub mod data_backend;
 pub mod data_types;
+pub mod db_migrations;
 pub mod db_operations;
+pub mod dialogue_storage;
+pub mod german_date_parser;
+pub mod i18n;
+pub mod meal_watches;
+pub mod price_parser;
+pub mod saxony_holidays;
+pub mod send_schedule;
 pub mod shared_main;
 pub mod task_scheduler_funcs;
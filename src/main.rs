@@ -0,0 +1,328 @@
+// GEANT_OV_RSA_CA_4_tcs-cert3.pem has to be properly set up, eg. in /etc/ssl/certs for Debian
+// (the container image already has it)
+use stuwe_telegram_rs::data_types::CampusDualData;
+
+use stuwe_telegram_rs::admin_commands::{
+    admin_callback_handler, broadcast_cmd, is_operator, stats_cmd, user_cmd,
+};
+use stuwe_telegram_rs::bot_command_handlers::{
+    admin_only_cmd, allergen_profile_cmd, allergene, cancel_cmd, change_mensa, day_cmd, diet_cmd,
+    follow_mensen, invalid_cmd, language_cmd, price_tier_cmd, reply_time_dialogue, senddiff,
+    settings_cmd, show_different_mensa, skip_empty, start, start_time_dialogue, subscribe, tag_cmd,
+    unsubscribe, unwatch_cmd, watch_cmd, week_cmd, weekdays_cmd,
+};
+use stuwe_telegram_rs::bot_command_helpers::sender_is_admin;
+use stuwe_telegram_rs::constants::{
+    ADMIN_CHAT_IDS, API_URL, BACKEND, CD_DATA, DB_FILENAME, MEAL_CACHE_TTL, MENSEN, MENSI_DB,
+    OLLAMA_HOST, OLLAMA_MODEL, OPENMENSA_DB, STUWE_DB, USER_REGISTRATIONS,
+};
+use stuwe_telegram_rs::data_backend::{
+    mm_parser::MensiMatesBackend, openmensa_parser::OpenMensaBackend,
+    stuwe_html_parser::StuWeHtmlBackend, stuwe_parser::StuWeBackend, MealBackend,
+};
+use stuwe_telegram_rs::data_types::{
+    Backend, Command, DialogueState, JobHandlerTask, JobHandlerTaskType, JobType,
+};
+use stuwe_telegram_rs::db_operations::check_or_create_db_tables;
+use stuwe_telegram_rs::dialogue_storage::SqliteDialogueStorage;
+use stuwe_telegram_rs::shared_main::{
+    callback_handler, inline_query_handler, my_chat_member_handler, set_command_menus, update_lang,
+};
+use stuwe_telegram_rs::task_scheduler_funcs::{
+    handle_add_registration_task, handle_broadcast_update_task, handle_delete_registration_task,
+    handle_remove_chat_task, handle_update_registration_task, handle_update_schedule_task,
+    load_jobs_from_db, start_meal_watch_job, start_mensaupd_hook_and_campusdual_job,
+};
+
+use clap::{error::ErrorKind, CommandFactory, Parser};
+use std::env;
+use std::sync::RwLock;
+use teloxide::{
+    dispatching::{dialogue, UpdateHandler},
+    prelude::*,
+};
+use tokio::sync::broadcast;
+use tokio_cron_scheduler::JobScheduler;
+
+/// Telegram bot to receive daily meal plans from any Studentenwerk Leipzig mensa.
+#[derive(Parser, Debug)]
+#[command(author, version, about)]
+struct Args {
+    /// The telegram bot token to be used
+    #[arg(short, long, env)]
+    token: String,
+    /// Where meal plans are fetched from
+    #[arg(short, long, env, value_enum, default_value_t = Backend::StuWe)]
+    backend: Backend,
+    /// Mensa-API URL (required by the StuWe backend)
+    #[arg(short, long, env)]
+    api_url: Option<String>,
+    /// OpenMensa feed v2 sources, comma separated (required by the OpenMensa backend){n}Each is a URL or file path of a canteen metadata or meal feed
+    #[arg(long, env, value_delimiter = ',')]
+    openmensa_feeds: Vec<String>,
+    /// Seconds a fetched meal plan is served from cache{n}If fetching fails, older cached plans are used regardless
+    #[arg(long, env, default_value_t = 300)]
+    cache_ttl: i64,
+    #[arg(short, long, env = "CD_USER", id = "CAMPUSDUAL-USER")]
+    user: Option<String>,
+    #[arg(short, long, env = "CD_PASSWORD", id = "CD-PASSWORD")]
+    password: Option<String>,
+    /// The Chat-ID which will receive CampusDual exam scores
+    #[arg(short, long, env)]
+    chatid: Option<i64>,
+    /// Enable debug logging (very bloated amount){n}[SETS env: RUST_LOG=debug]
+    #[arg(long)]
+    debug: bool,
+    /// Ollama API host for AI time parsing bloatware{n}Example: <http://127.0.0.1:11434/api>
+    #[arg(long, env = "OLLAMA_HOST")]
+    ollama_host: Option<String>,
+    /// Ollama model for inference{n}Example: 'llama3:latest'
+    #[arg(long, env = "OLLAMA_MODEL")]
+    ollama_model: Option<String>,
+    /// Chat-IDs that may use /stats, /broadcast and /user, comma separated
+    #[arg(long, env, value_delimiter = ',')]
+    admin_chat_ids: Vec<i64>,
+}
+
+#[tokio::main]
+async fn main() {
+    //// Args setup
+    let args = Args::parse();
+
+    let (db_filename, meal_backend): (&str, Box<dyn MealBackend>) = match args.backend {
+        Backend::StuWe => {
+            let Some(api_url) = args.api_url else {
+                Args::command()
+                    .error(
+                        ErrorKind::MissingRequiredArgument,
+                        "--api-url is required for the stuwe backend",
+                    )
+                    .exit();
+            };
+            API_URL.set(api_url).unwrap();
+            (STUWE_DB, Box::new(StuWeBackend))
+        }
+        Backend::StuWeHtml => (STUWE_DB, Box::new(StuWeHtmlBackend)),
+        Backend::MensiMates => (MENSI_DB, Box::new(MensiMatesBackend)),
+        Backend::OpenMensa => {
+            if args.openmensa_feeds.is_empty() {
+                Args::command()
+                    .error(
+                        ErrorKind::MissingRequiredArgument,
+                        "--openmensa-feeds is required for the openmensa backend",
+                    )
+                    .exit();
+            }
+            (
+                OPENMENSA_DB,
+                Box::new(OpenMensaBackend::new(args.openmensa_feeds)),
+            )
+        }
+    };
+    DB_FILENAME.set(db_filename).unwrap();
+    MEAL_CACHE_TTL
+        .set(chrono::Duration::seconds(args.cache_ttl))
+        .unwrap();
+    BACKEND.set(meal_backend).unwrap();
+    OLLAMA_HOST.set(args.ollama_host).unwrap();
+    OLLAMA_MODEL.set(args.ollama_model).unwrap();
+    ADMIN_CHAT_IDS.set(args.admin_chat_ids).unwrap();
+
+    if let (Some(username), Some(password), Some(chat_id)) = (args.user, args.password, args.chatid)
+    {
+        CD_DATA
+            .set(CampusDualData {
+                username,
+                password,
+                chat_id,
+            })
+            .unwrap();
+    } else {
+        log::info!("CampusDual support disabled")
+    }
+
+    if args.debug {
+        env::set_var("RUST_LOG", "debug");
+    } else if env::var(pretty_env_logger::env_logger::DEFAULT_FILTER_ENV).is_err() {
+        env::set_var("RUST_LOG", "info");
+    }
+
+    pretty_env_logger::init_timed();
+    log::info!("Starting bot...");
+
+    //// DB setup
+    check_or_create_db_tables().unwrap();
+
+    let mensen = match BACKEND.get().unwrap().get_mensen().await {
+        Ok(mensen) => mensen,
+        Err(e) => {
+            log::error!("Mensa API call failed: {}", e);
+            return;
+        }
+    };
+    MENSEN.set(mensen.clone()).unwrap();
+
+    let bot = Bot::new(args.token);
+    if let Err(e) = set_command_menus(&bot).await {
+        log::error!("Setting command menus failed: {}", e);
+    }
+
+    let (jobhandler_task_tx, jobhandler_task_rx): JobHandlerTaskType = broadcast::channel(10);
+
+    // every user has a mensa_id, but only users with auto send have job_uuids inside RegistrEntry
+    {
+        let bot = bot.clone();
+        // there is effectively only one tx and rx, however since rx cant be passed as dptree dep (?!),
+        // tx has to be cloned and passed to both (inside command_handler it will be resubscribed to rx)
+        let jobhandler_task_tx = jobhandler_task_tx.clone();
+        tokio::spawn(async move {
+            log::info!("Starting task scheduler...");
+            run_task_scheduler(bot, jobhandler_task_tx, jobhandler_task_rx).await;
+        });
+    }
+
+    // passing a receiver doesnt work for some reason, so sending user_registration_data_tx and resubscribing to get rx
+    let command_handler_deps =
+        dptree::deps![SqliteDialogueStorage::new(), mensen, jobhandler_task_tx];
+    Dispatcher::builder(bot, schema())
+        .dependencies(command_handler_deps)
+        .enable_ctrlc_handler()
+        .build()
+        .dispatch()
+        .await;
+}
+
+fn schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>> {
+    use dptree::case;
+
+    // commands for other bots ("/heute@otherbot") don't parse and end up at invalid_cmd
+    let command_handler = teloxide::filter_command::<Command, _>()
+        .branch(
+            dptree::filter(|cmd: Command| cmd.is_operator_only())
+                .branch(
+                    dptree::filter(|msg: Message| is_operator(msg.chat.id))
+                        .branch(case![Command::Stats].endpoint(stats_cmd))
+                        .branch(case![Command::Broadcast(text)].endpoint(broadcast_cmd))
+                        .branch(case![Command::User(chat_id)].endpoint(user_cmd)),
+                )
+                .endpoint(invalid_cmd),
+        )
+        .branch(
+            dptree::filter_async(|bot: Bot, msg: Message, cmd: Command| async move {
+                cmd.changes_settings() && !sender_is_admin(&bot, &msg).await
+            })
+            .endpoint(admin_only_cmd),
+        )
+        .branch(dptree::case![Command::Start].endpoint(start))
+        .branch(dptree::case![Command::Heute].endpoint(day_cmd))
+        .branch(dptree::case![Command::Morgen].endpoint(day_cmd))
+        .branch(dptree::case![Command::Übermorgen].endpoint(day_cmd))
+        .branch(dptree::case![Command::Woche].endpoint(week_cmd))
+        .branch(dptree::case![Command::Tag(date)].endpoint(tag_cmd))
+        .branch(dptree::case![Command::Andere].endpoint(show_different_mensa))
+        .branch(dptree::case![Command::Subscribe].endpoint(subscribe))
+        .branch(dptree::case![Command::Unsubscribe].endpoint(unsubscribe))
+        .branch(dptree::case![Command::Mensa].endpoint(change_mensa))
+        .branch(dptree::case![Command::Mensen].endpoint(follow_mensen))
+        .branch(dptree::case![Command::Allergene].endpoint(allergene))
+        .branch(dptree::case![Command::Diff].endpoint(senddiff))
+        .branch(dptree::case![Command::Auslassen].endpoint(skip_empty))
+        .branch(dptree::case![Command::Ernaehrung].endpoint(diet_cmd))
+        .branch(dptree::case![Command::Allergieprofil].endpoint(allergen_profile_cmd))
+        .branch(dptree::case![Command::Preise].endpoint(price_tier_cmd))
+        .branch(dptree::case![Command::Einstellungen].endpoint(settings_cmd))
+        .branch(dptree::case![Command::Merken(keyword)].endpoint(watch_cmd))
+        .branch(dptree::case![Command::Vergessen(keyword)].endpoint(unwatch_cmd))
+        .branch(dptree::case![Command::Uhrzeit].endpoint(start_time_dialogue))
+        .branch(dptree::case![Command::Wochentage].endpoint(weekdays_cmd))
+        .branch(dptree::case![Command::Sprache].endpoint(language_cmd))
+        .branch(dptree::case![Command::Abbrechen].endpoint(cancel_cmd));
+
+    let message_handler = Update::filter_message()
+        .branch(command_handler)
+        .branch(
+            case![DialogueState::AwaitTimeReply]
+                .filter_async(
+                    |bot: Bot, msg: Message| async move { sender_is_admin(&bot, &msg).await },
+                )
+                .endpoint(reply_time_dialogue),
+        )
+        .branch(dptree::endpoint(invalid_cmd));
+
+    let callback_query_handler = Update::filter_callback_query()
+        .branch(
+            dptree::filter(|q: CallbackQuery| {
+                q.data
+                    .as_deref()
+                    .is_some_and(|data| data.starts_with("adm_"))
+            })
+            .endpoint(admin_callback_handler),
+        )
+        .endpoint(callback_handler);
+
+    // inline queries have no chat, so they can't enter a dialogue
+    let inline_query_handler = Update::filter_inline_query().endpoint(inline_query_handler);
+
+    // blocked by a user or removed from a group
+    let my_chat_member_handler = Update::filter_my_chat_member().endpoint(my_chat_member_handler);
+
+    dptree::entry()
+        .branch(inline_query_handler)
+        .branch(my_chat_member_handler)
+        .branch(
+            dialogue::enter::<Update, SqliteDialogueStorage, DialogueState, _>()
+                .map(update_lang)
+                .branch(message_handler)
+                .branch(callback_query_handler),
+        )
+}
+
+async fn run_task_scheduler(
+    bot: Bot,
+    jobhandler_task_tx: broadcast::Sender<JobHandlerTask>,
+    mut jobhandler_task_rx: broadcast::Receiver<JobHandlerTask>,
+) {
+    let sched = JobScheduler::new().await.unwrap();
+
+    start_mensaupd_hook_and_campusdual_job(bot.clone(), &sched, jobhandler_task_tx.clone()).await;
+    start_meal_watch_job(bot.clone(), &sched).await;
+
+    let user_registrations = load_jobs_from_db(&bot, &sched).await;
+    USER_REGISTRATIONS
+        .set(RwLock::new(user_registrations))
+        .unwrap();
+
+    // start scheduler (non blocking)
+    sched.start().await.unwrap();
+
+    log::info!("Ready.");
+
+    // receive job update msg (register/unregister/check existence)
+    while let Ok(job_handler_task) = jobhandler_task_rx.recv().await {
+        match job_handler_task.job_type {
+            JobType::Register => {
+                handle_add_registration_task(&bot, job_handler_task, &sched).await;
+            }
+
+            JobType::UpdateRegistration => {
+                handle_update_registration_task(&bot, job_handler_task, &sched).await;
+            }
+
+            JobType::UpdateSchedule => {
+                handle_update_schedule_task(&bot, job_handler_task, &sched).await;
+            }
+
+            JobType::DeleteRegistration => {
+                handle_delete_registration_task(job_handler_task, &sched).await;
+            }
+
+            JobType::BroadcastUpdate => {
+                handle_broadcast_update_task(&bot, job_handler_task, &sched).await;
+            }
+
+            JobType::RemoveChat => {
+                handle_remove_chat_task(job_handler_task, &sched).await;
+            }
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, Duration, Local, NaiveDate};
+use teloxide::prelude::*;
+use tokio_cron_scheduler::JobScheduler;
+
+use crate::{
+    constants::WATCH_SCAN_DAYS,
+    data_backend::{get_meals_cached, mensa_name},
+    data_types::meal_data_types::MealGroup,
+    db_operations::{get_all_watches, save_watch_notified, was_watch_notified},
+    i18n::date_fmt,
+    shared_main::get_user_registration,
+    task_scheduler_funcs::handle_send_error,
+};
+
+/// Names of the meals (incl. variations) that contain one of the keywords, case insensitive
+pub fn find_watched_meals(meal_groups: &[MealGroup], keywords: &[String]) -> Vec<String> {
+    let keywords: Vec<String> = keywords.iter().map(|kw| kw.to_lowercase()).collect();
+    let matches_keyword = |name: &str| {
+        let name = name.to_lowercase();
+        keywords.iter().any(|kw| name.contains(kw))
+    };
+
+    let mut found = vec![];
+    for meal in meal_groups.iter().flat_map(|group| &group.sub_meals) {
+        let variation_matches = meal
+            .variations
+            .iter()
+            .flatten()
+            .any(|variation| matches_keyword(&variation.name));
+
+        if (matches_keyword(&meal.name) || variation_matches) && !found.contains(&meal.name) {
+            found.push(meal.name.clone());
+        }
+    }
+
+    found
+}
+
+/// Weekdays from today up to `WATCH_SCAN_DAYS` ahead
+fn upcoming_days(today: NaiveDate) -> Vec<NaiveDate> {
+    (0..WATCH_SCAN_DAYS)
+        .map(|offset| today + Duration::days(offset))
+        .filter(|date| date.weekday().num_days_from_monday() < 5)
+        .collect()
+}
+
+/// Notifies every chat once per watched meal and date
+pub async fn scan_meal_watches(bot: &Bot, sched: &JobScheduler) {
+    let watches = match get_all_watches().await {
+        Ok(watches) => watches,
+        Err(e) => {
+            log::error!("Reading watches failed: {}", e);
+            return;
+        }
+    };
+
+    let mut keywords_per_chat: BTreeMap<i64, Vec<String>> = BTreeMap::new();
+    for (chat_id, keyword) in watches {
+        keywords_per_chat.entry(chat_id).or_default().push(keyword);
+    }
+
+    let days = upcoming_days(Local::now().date_naive());
+
+    for (chat_id, keywords) in keywords_per_chat {
+        let Some(registration) = get_user_registration(chat_id) else {
+            continue;
+        };
+
+        // (date, mensa_id, meal name)
+        let mut new_matches: Vec<(NaiveDate, u32, String)> = vec![];
+        for date in &days {
+            for mensa_id in registration.mensa_ids() {
+                // fetch errors are retried on the next scan
+                let Ok(meal_plan) = get_meals_cached(mensa_id, *date).await else {
+                    continue;
+                };
+
+                for meal_name in find_watched_meals(&meal_plan.meal_groups, &keywords) {
+                    let already_known = new_matches
+                        .iter()
+                        .any(|(d, _, name)| d == date && *name == meal_name);
+                    if !already_known
+                        && !was_watch_notified(chat_id, *date, &meal_name)
+                            .await
+                            .unwrap_or(true)
+                    {
+                        new_matches.push((*date, mensa_id, meal_name));
+                    }
+                }
+            }
+        }
+
+        if new_matches.is_empty() {
+            continue;
+        }
+
+        let mut msg = String::from(registration.settings.lang.texts().watch_found);
+        for (date, mensa_id, meal_name) in &new_matches {
+            msg += &format!(
+                "\n\n{}\n{}: {}",
+                date_fmt(*date, registration.settings.lang),
+                mensa_name(*mensa_id),
+                meal_name
+            );
+        }
+
+        match bot.send_message(ChatId(chat_id), msg).await {
+            Ok(_) => {
+                for (date, _, meal_name) in &new_matches {
+                    if let Err(e) = save_watch_notified(chat_id, *date, meal_name).await {
+                        log::error!("Saving watch notification failed: {}", e);
+                    }
+                }
+            }
+            Err(e) => handle_send_error(chat_id, e, sched).await,
+        }
+    }
+}
@@ -0,0 +1,187 @@
+use regex_lite::Regex;
+use static_init::dynamic;
+
+use crate::data_types::PriceTier;
+
+/// Prices of a meal in cent, `None` if the tier isn't listed
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct MealPrices {
+    pub student: Option<u32>,
+    pub staff: Option<u32>,
+    pub guest: Option<u32>,
+}
+
+impl MealPrices {
+    /// Price of one tier, `PriceTier::All` has no single price
+    pub fn get(&self, tier: PriceTier) -> Option<u32> {
+        match tier {
+            PriceTier::All => None,
+            PriceTier::Student => self.student,
+            PriceTier::Staff => self.staff,
+            PriceTier::Guest => self.guest,
+        }
+    }
+}
+
+/// Parses price strings like "3,20 € / 5,20 € / 6,50 €" (student / staff / guest),
+/// "Studierende: 2.50€ | Gäste: 4.90€" or a single price for everyone.
+///
+/// Returns `None` if the string contains no price at all.
+pub fn parse_prices(raw: &str) -> Option<MealPrices> {
+    #[dynamic]
+    static PRICE_RE: Regex = Regex::new(r"(\d+)[,.](\d{1,2})|(\d+)\s*(?:€|EUR|Euro)").unwrap();
+
+    // (label tier, price) of every part
+    let parts: Vec<(Option<PriceTier>, Option<u32>)> = raw
+        .split(['/', '|', '\n'])
+        .map(|part| {
+            let cents = PRICE_RE.captures(part).and_then(|caps| match caps.get(3) {
+                Some(euros) => Some(euros.as_str().parse::<u32>().ok()? * 100),
+                // "2,5" is 2,50
+                None => Some(
+                    caps[1].parse::<u32>().ok()? * 100
+                        + format!("{:0<2}", &caps[2]).parse::<u32>().ok()?,
+                ),
+            });
+            (label_tier(&part.to_lowercase()), cents)
+        })
+        .collect();
+
+    let priced: Vec<&(Option<PriceTier>, Option<u32>)> =
+        parts.iter().filter(|(_, cents)| cents.is_some()).collect();
+
+    match priced.as_slice() {
+        [] => None,
+        // a single unlabeled price applies to everyone
+        [(None, cents)] => Some(MealPrices {
+            student: *cents,
+            staff: *cents,
+            guest: *cents,
+        }),
+        _ if priced.iter().any(|(tier, _)| tier.is_some()) => {
+            let mut prices = MealPrices::default();
+            for (tier, cents) in priced {
+                match tier {
+                    Some(PriceTier::Student) => prices.student = *cents,
+                    Some(PriceTier::Staff) => prices.staff = *cents,
+                    Some(PriceTier::Guest) => prices.guest = *cents,
+                    _ => {}
+                }
+            }
+            Some(prices)
+        }
+        // positional, parts without a price (e.g. "-") keep their slot.
+        // two parts are student / staff, as OpenMensa feeds without a guest price are joined
+        _ => Some(MealPrices {
+            student: parts.first().and_then(|(_, cents)| *cents),
+            staff: parts.get(1).and_then(|(_, cents)| *cents),
+            guest: parts.get(2).and_then(|(_, cents)| *cents),
+        }),
+    }
+}
+
+fn label_tier(part: &str) -> Option<PriceTier> {
+    if part.contains("stud") {
+        Some(PriceTier::Student)
+    } else if ["bedienst", "mitarbeit", "angestellt", "employee", "staff"]
+        .iter()
+        .any(|label| part.contains(label))
+    {
+        Some(PriceTier::Staff)
+    } else if ["gäst", "gast", "guest", "other"]
+        .iter()
+        .any(|label| part.contains(label))
+    {
+        Some(PriceTier::Guest)
+    } else {
+        None
+    }
+}
+
+/// "3,20 €"
+pub fn format_cents(cents: u32) -> String {
+    format!("{},{:02} €", cents / 100, cents % 100)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prices(student: Option<u32>, staff: Option<u32>, guest: Option<u32>) -> MealPrices {
+        MealPrices {
+            student,
+            staff,
+            guest,
+        }
+    }
+
+    #[test]
+    fn single_price_applies_to_everyone() {
+        assert_eq!(
+            parse_prices("3,20 €"),
+            Some(prices(Some(320), Some(320), Some(320)))
+        );
+        assert_eq!(
+            parse_prices("4 Euro"),
+            Some(prices(Some(400), Some(400), Some(400)))
+        );
+    }
+
+    #[test]
+    fn labelled_parts() {
+        assert_eq!(
+            parse_prices("Studierende: 2.50€ | Gäste: 4.90€"),
+            Some(prices(Some(250), None, Some(490)))
+        );
+        assert_eq!(
+            parse_prices("Bedienstete: 4,90 € / Studierende: 2,90 €"),
+            Some(prices(Some(290), Some(490), None))
+        );
+    }
+
+    #[test]
+    fn positional_parts() {
+        assert_eq!(
+            parse_prices("3,20 € / 5,20 € / 6,50 €"),
+            Some(prices(Some(320), Some(520), Some(650)))
+        );
+        assert_eq!(
+            parse_prices("2,50 € / - / 5,50 €"),
+            Some(prices(Some(250), None, Some(550)))
+        );
+        assert_eq!(
+            parse_prices("2,50 € / 4,90 €"),
+            Some(prices(Some(250), Some(490), None))
+        );
+    }
+
+    #[test]
+    fn short_cents_are_padded() {
+        assert_eq!(
+            parse_prices("2,5 €"),
+            Some(prices(Some(250), Some(250), Some(250)))
+        );
+    }
+
+    #[test]
+    fn no_price() {
+        assert_eq!(parse_prices(""), None);
+        assert_eq!(parse_prices("gratis"), None);
+        assert_eq!(parse_prices("- / -"), None);
+    }
+
+    #[test]
+    fn get_tier() {
+        let parsed = prices(Some(320), Some(520), None);
+        assert_eq!(parsed.get(PriceTier::Student), Some(320));
+        assert_eq!(parsed.get(PriceTier::Guest), None);
+        assert_eq!(parsed.get(PriceTier::All), None);
+    }
+
+    #[test]
+    fn cents_formatting() {
+        assert_eq!(format_cents(320), "3,20 €");
+        assert_eq!(format_cents(5), "0,05 €");
+        assert_eq!(format_cents(1000), "10,00 €");
+    }
+}
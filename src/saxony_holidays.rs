@@ -0,0 +1,112 @@
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+
+/// Name of the public holiday in Saxony at `date`, if any
+pub fn saxony_holiday(date: NaiveDate) -> Option<&'static str> {
+    let fixed = match (date.month(), date.day()) {
+        (1, 1) => Some("Neujahr"),
+        (5, 1) => Some("Tag der Arbeit"),
+        (10, 3) => Some("Tag der Deutschen Einheit"),
+        (10, 31) => Some("Reformationstag"),
+        (12, 25) => Some("1. Weihnachtsfeiertag"),
+        (12, 26) => Some("2. Weihnachtsfeiertag"),
+        _ => None,
+    };
+    if fixed.is_some() {
+        return fixed;
+    }
+
+    if date == buss_und_bettag(date.year()) {
+        return Some("Buß- und Bettag");
+    }
+
+    let easter = easter_sunday(date.year())?;
+    match (date - easter).num_days() {
+        -2 => Some("Karfreitag"),
+        0 => Some("Ostersonntag"),
+        1 => Some("Ostermontag"),
+        39 => Some("Christi Himmelfahrt"),
+        49 => Some("Pfingstsonntag"),
+        50 => Some("Pfingstmontag"),
+        _ => None,
+    }
+}
+
+/// Gregorian easter sunday (anonymous Gregorian algorithm)
+pub fn easter_sunday(year: i32) -> Option<NaiveDate> {
+    let a = year % 19;
+    let b = year / 100;
+    let c = year % 100;
+    let d = b / 4;
+    let e = b % 4;
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let i = c / 4;
+    let k = c % 4;
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+
+    NaiveDate::from_ymd_opt(year, month as u32, day as u32)
+}
+
+/// Wednesday before november 23rd
+fn buss_und_bettag(year: i32) -> NaiveDate {
+    let nov_22 = NaiveDate::from_ymd_opt(year, 11, 22).unwrap();
+    let days_back =
+        (nov_22.weekday().num_days_from_monday() + 7 - Weekday::Wed.num_days_from_monday()) % 7;
+    nov_22 - Duration::days(days_back.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn easter() {
+        assert_eq!(easter_sunday(2024), Some(date(2024, 3, 31)));
+        assert_eq!(easter_sunday(2025), Some(date(2025, 4, 20)));
+        assert_eq!(easter_sunday(2026), Some(date(2026, 4, 5)));
+        // earliest and latest in this century
+        assert_eq!(easter_sunday(2008), Some(date(2008, 3, 23)));
+        assert_eq!(easter_sunday(2038), Some(date(2038, 4, 25)));
+    }
+
+    #[test]
+    fn easter_based_holidays() {
+        assert_eq!(saxony_holiday(date(2025, 4, 18)), Some("Karfreitag"));
+        assert_eq!(saxony_holiday(date(2025, 4, 21)), Some("Ostermontag"));
+        assert_eq!(
+            saxony_holiday(date(2025, 5, 29)),
+            Some("Christi Himmelfahrt")
+        );
+        assert_eq!(saxony_holiday(date(2025, 6, 9)), Some("Pfingstmontag"));
+        assert_eq!(saxony_holiday(date(2026, 4, 3)), Some("Karfreitag"));
+        assert_eq!(saxony_holiday(date(2026, 5, 25)), Some("Pfingstmontag"));
+    }
+
+    #[test]
+    fn buss_und_bettag_dates() {
+        assert_eq!(buss_und_bettag(2023), date(2023, 11, 22));
+        assert_eq!(buss_und_bettag(2024), date(2024, 11, 20));
+        assert_eq!(buss_und_bettag(2025), date(2025, 11, 19));
+        assert_eq!(buss_und_bettag(2026), date(2026, 11, 18));
+        assert_eq!(saxony_holiday(date(2025, 11, 19)), Some("Buß- und Bettag"));
+    }
+
+    #[test]
+    fn fixed_and_regular_days() {
+        assert_eq!(saxony_holiday(date(2025, 10, 31)), Some("Reformationstag"));
+        assert_eq!(
+            saxony_holiday(date(2025, 12, 26)),
+            Some("2. Weihnachtsfeiertag")
+        );
+        assert_eq!(saxony_holiday(date(2025, 10, 24)), None);
+        assert_eq!(saxony_holiday(date(2025, 4, 17)), None);
+    }
+}
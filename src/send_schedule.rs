@@ -0,0 +1,257 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Datelike, FixedOffset, Local, NaiveDate, Offset, TimeZone};
+
+const CRON_WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+/// Time source of the send jobs, the local time in the bot and a fixed one in tests
+pub trait Clock: Clone + Send + Sync + 'static {
+    type Tz: TimeZone;
+
+    fn now(&self) -> DateTime<Self::Tz>;
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct LocalClock;
+
+impl Clock for LocalClock {
+    type Tz = Local;
+
+    fn now(&self) -> DateTime<Local> {
+        Local::now()
+    }
+}
+
+/// (UTC cron schedule, zone offset) of every job needed for `delivery_times`
+pub fn send_jobs<C: Clock>(
+    clock: &C,
+    delivery_times: &BTreeMap<(u32, u32), Vec<u32>>,
+) -> Vec<(String, FixedOffset)> {
+    let now = clock.now();
+    let offsets = zone_utc_offsets(&now.timezone(), now.year());
+
+    delivery_times
+        .iter()
+        .flat_map(|(&(hour, minute), weekdays)| {
+            offsets
+                .iter()
+                .map(move |&offset| (utc_cron_schedule(hour, minute, weekdays, offset), offset))
+        })
+        .collect()
+}
+
+/// Local date a firing job of `offset` sends the plan for, `None` if it isn't the active one
+pub fn active_send_date<C: Clock>(clock: &C, offset: FixedOffset) -> Option<NaiveDate> {
+    let now = clock.now();
+    is_active_offset(&now, offset).then(|| now.date_naive())
+}
+
+/// UTC offsets `tz` uses in `year`: one, or two for zones with DST (e.g. CET and CEST).
+///
+/// The timezone is the local one (`TZ` env variable, Europe/Berlin in the container).
+pub fn zone_utc_offsets<Tz: TimeZone>(tz: &Tz, year: i32) -> Vec<FixedOffset> {
+    let mut offsets: Vec<FixedOffset> = [1, 7]
+        .iter()
+        .filter_map(|month| NaiveDate::from_ymd_opt(year, *month, 1))
+        .map(|date| tz.offset_from_utc_date(&date).fix())
+        .collect();
+
+    offsets.dedup();
+    offsets
+}
+
+/// UTC cron schedule of a local send time, valid while the zone is at `offset`
+pub fn utc_cron_schedule(hour: u32, minute: u32, weekdays: &[u32], offset: FixedOffset) -> String {
+    let local_minutes = i64::from(hour * 60 + minute);
+    let utc_minutes = local_minutes - i64::from(offset.local_minus_utc() / 60);
+
+    // e.g. 00:30 CEST is 22:30 UTC of the previous day
+    let day_shift = utc_minutes.div_euclid(24 * 60);
+    let utc_minutes = utc_minutes.rem_euclid(24 * 60);
+
+    let cron_days = weekdays
+        .iter()
+        .map(|day| CRON_WEEKDAYS[(i64::from(*day) + day_shift).rem_euclid(7) as usize])
+        .collect::<Vec<&str>>()
+        .join(",");
+
+    format!(
+        "0 {} {} * * {}",
+        utc_minutes % 60,
+        utc_minutes / 60,
+        cron_days
+    )
+}
+
+/// Whether a job scheduled for `offset` has to send at `now`.
+///
+/// Every send time has one job per zone offset, so only the one matching
+/// the current offset sends and the others fire an hour off without effect.
+pub fn is_active_offset<Tz: TimeZone>(now: &DateTime<Tz>, offset: FixedOffset) -> bool {
+    now.offset().fix() == offset
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, LocalResult, NaiveDateTime, NaiveTime, Utc, Weekday};
+
+    /// Europe/Berlin, switching on the last sunday of march and october at 01:00 UTC
+    #[derive(Debug, Clone, Copy)]
+    struct Berlin;
+
+    fn cet() -> FixedOffset {
+        FixedOffset::east_opt(3600).unwrap()
+    }
+
+    fn cest() -> FixedOffset {
+        FixedOffset::east_opt(2 * 3600).unwrap()
+    }
+
+    fn last_sunday(year: i32, month: u32) -> NaiveDate {
+        NaiveDate::from_weekday_of_month_opt(year, month, Weekday::Sun, 5)
+            .or_else(|| NaiveDate::from_weekday_of_month_opt(year, month, Weekday::Sun, 4))
+            .unwrap()
+    }
+
+    impl Berlin {
+        fn offset_at(utc: &NaiveDateTime) -> FixedOffset {
+            let switch = |month| last_sunday(utc.year(), month).and_hms_opt(1, 0, 0).unwrap();
+            match (switch(3)..switch(10)).contains(utc) {
+                true => cest(),
+                false => cet(),
+            }
+        }
+    }
+
+    impl TimeZone for Berlin {
+        type Offset = FixedOffset;
+
+        fn from_offset(_: &FixedOffset) -> Self {
+            Berlin
+        }
+
+        fn offset_from_local_date(&self, local: &NaiveDate) -> LocalResult<FixedOffset> {
+            self.offset_from_local_datetime(&local.and_time(NaiveTime::MIN))
+        }
+
+        fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<FixedOffset> {
+            let fitting: Vec<FixedOffset> = [cet(), cest()]
+                .into_iter()
+                .filter(|offset| {
+                    let utc = *local - Duration::seconds(offset.local_minus_utc().into());
+                    Berlin::offset_at(&utc) == *offset
+                })
+                .collect();
+            match fitting[..] {
+                [offset] => LocalResult::Single(offset),
+                [a, b] => LocalResult::Ambiguous(a, b),
+                _ => LocalResult::None,
+            }
+        }
+
+        fn offset_from_utc_date(&self, utc: &NaiveDate) -> FixedOffset {
+            Berlin::offset_at(&utc.and_time(NaiveTime::MIN))
+        }
+
+        fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> FixedOffset {
+            Berlin::offset_at(utc)
+        }
+    }
+
+    #[derive(Debug, Clone, Copy)]
+    struct FixedClock(NaiveDateTime);
+
+    impl Clock for FixedClock {
+        type Tz = Berlin;
+
+        fn now(&self) -> DateTime<Berlin> {
+            Utc.from_utc_datetime(&self.0).with_timezone(&Berlin)
+        }
+    }
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, month, day).unwrap()
+    }
+
+    /// UTC time a job of `utc_cron_schedule` fires on `day`, if it does
+    fn fire_time(cron: &str, day: NaiveDate) -> Option<NaiveDateTime> {
+        let fields: Vec<&str> = cron.split(' ').collect();
+        let weekday = CRON_WEEKDAYS[day.weekday().num_days_from_monday() as usize];
+        fields[5].split(',').any(|d| d == weekday).then(|| {
+            day.and_hms_opt(fields[2].parse().unwrap(), fields[1].parse().unwrap(), 0)
+                .unwrap()
+        })
+    }
+
+    /// Local times of all sends from `from` to `to` (UTC days)
+    fn sends(hour: u32, minute: u32, from: NaiveDate, to: NaiveDate) -> Vec<NaiveDateTime> {
+        let delivery_times = BTreeMap::from([((hour, minute), (0..7).collect())]);
+        let jobs = send_jobs(&FixedClock(from.and_time(NaiveTime::MIN)), &delivery_times);
+        assert_eq!(jobs.len(), 2);
+
+        let mut sends = vec![];
+        for day in from.iter_days().take_while(|day| *day <= to) {
+            for (cron, offset) in &jobs {
+                let Some(fired) = fire_time(cron, day) else {
+                    continue;
+                };
+                let clock = FixedClock(fired);
+                if let Some(send_date) = active_send_date(&clock, *offset) {
+                    let local = clock.now().naive_local();
+                    assert_eq!(send_date, local.date());
+                    sends.push(local);
+                }
+            }
+        }
+        sends.sort();
+        sends
+    }
+
+    #[test]
+    fn berlin_has_cet_and_cest() {
+        assert_eq!(zone_utc_offsets(&Berlin, 2026), [cet(), cest()]);
+        assert_eq!(zone_utc_offsets(&Utc, 2026), [Utc.fix()]);
+    }
+
+    #[test]
+    fn cron_day_shift() {
+        // 00:30 local is still the previous day in UTC
+        assert_eq!(utc_cron_schedule(0, 30, &[0], cet()), "0 30 23 * * Sun");
+        assert_eq!(utc_cron_schedule(0, 30, &[0], cest()), "0 30 22 * * Sun");
+        assert_eq!(
+            utc_cron_schedule(0, 30, &[6, 0], cet()),
+            "0 30 23 * * Sat,Sun"
+        );
+        assert_eq!(
+            utc_cron_schedule(6, 0, &[0, 4], cest()),
+            "0 0 4 * * Mon,Fri"
+        );
+    }
+
+    #[test]
+    fn one_send_per_day_around_switches() {
+        // 2026-03-29: CET -> CEST, 2026-10-25: CEST -> CET
+        for switch_day in [date(3, 29), date(10, 25)] {
+            for (hour, minute) in [(6, 0), (0, 30), (23, 45)] {
+                let from = switch_day - Duration::days(3);
+                let to = switch_day + Duration::days(3);
+                let expected: Vec<NaiveDateTime> = (from.iter_days())
+                    .skip(1)
+                    .take_while(|day| *day < to)
+                    .map(|day| day.and_hms_opt(hour, minute, 0).unwrap())
+                    .collect();
+
+                let sends: Vec<NaiveDateTime> = sends(hour, minute, from, to)
+                    .into_iter()
+                    .filter(|send| expected.first() <= Some(send) && expected.last() >= Some(send))
+                    .collect();
+                assert_eq!(
+                    sends, expected,
+                    "{:02}:{:02} around {}",
+                    hour, minute, switch_day
+                );
+            }
+        }
+    }
+}
@@ -1,12 +1,17 @@
 use std::{collections::BTreeMap, error::Error, time::Instant};
 
-use chrono::Timelike;
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, Timelike, Weekday};
 use teloxide::{
     prelude::*,
-    types::{KeyboardButton, KeyboardMarkup},
-    utils::{command::BotCommands, markdown},
+    types::{
+        BotCommandScope, ChatMemberUpdated, InlineKeyboardButtonKind, InlineQueryResult,
+        InlineQueryResultArticle, InputMessageContent, InputMessageContentText, KeyboardButton,
+        KeyboardMarkup, MaybeInaccessibleMessage, Recipient, User,
+    },
+    utils::markdown,
 };
 use teloxide_core::{
+    errors::{ApiError, RequestError},
     types::{InlineKeyboardButton, InlineKeyboardMarkup, ParseMode},
     Bot,
 };
@@ -15,22 +20,68 @@ use tokio_cron_scheduler::{Job, JobScheduler};
 use uuid::Uuid;
 
 use crate::{
-    constants::{BACKEND, NO_DB_MSG, USER_REGISTRATIONS},
-    data_backend::{mm_parser::mm_build_meal_msg, stuwe_parser::stuwe_build_meal_msg},
-    data_types::{Backend, Command, MensaKeyboardAction, RegisterTask, UpdateRegistrationTask},
+    allergen_parser::{allergen_bit, ALLERGENS},
+    bot_command_helpers::is_chat_admin,
+    constants::{INLINE_MAX_RESULTS, USER_REGISTRATIONS, WEEK_ROLLOVER_HOUR},
+    data_backend::{
+        any_meals, build_meal_msg, mensa_name, resolve_days_forward, MealRenderOptions,
+    },
+    data_types::{
+        DialogueState, DialogueType, HandlerResult, MensaKeyboardAction, RegisterTask,
+        RemoveChatTask, UnregisterTask, UpdateRegistrationTask,
+    },
+    i18n::{bot_commands, date_fmt, help_text, Lang},
 };
 use crate::{
-    data_types::{JobHandlerTask, RegistrationEntry},
-    db_operations::update_db_row,
+    data_types::{
+        AllergenMode, Diet, DietMode, JobHandlerTask, PriceTier, RegistrationEntry,
+        UpdateScheduleTask, WeekSchedule,
+    },
+    db_operations::{
+        get_user_watches, remove_watch, save_user_schedule, save_user_settings,
+        set_additional_mensa, update_db_row,
+    },
+    german_date_parser::parse_german_date,
+    saxony_holidays::saxony_holiday,
+    send_schedule::{active_send_date, send_jobs, LocalClock},
+    task_scheduler_funcs::handle_send_error,
 };
 
+const SETTINGS_BACK: &str = "set:main";
+
 pub fn get_user_registration(chat_id: i64) -> Option<RegistrationEntry> {
     let user_data = USER_REGISTRATIONS
         .get()
-        .and_then(|data| data.read().unwrap().get(&chat_id).copied());
+        .and_then(|data| data.read().unwrap().get(&chat_id).cloned());
     user_data
 }
 
+/// Language of a chat, for unregistered chats the one of the user's Telegram app
+pub fn chat_lang(chat_id: ChatId, user: Option<&User>) -> Lang {
+    match get_user_registration(chat_id.0) {
+        Some(registration) => registration.settings.lang,
+        None => Lang::from_code(user.and_then(|user| user.language_code.as_deref())),
+    }
+}
+
+/// Passed to every chat handler as dependency
+pub fn update_lang(update: Update) -> Lang {
+    match update.chat() {
+        Some(chat) => chat_lang(chat.id, update.from()),
+        None => Lang::from_code(update.from().and_then(|user| user.language_code.as_deref())),
+    }
+}
+
+/// Command menu for every language, English is the default for all but German clients
+pub async fn set_command_menus(bot: &Bot) -> Result<(), RequestError> {
+    bot.set_my_commands(bot_commands(Lang::En)).await?;
+    bot.set_my_commands(bot_commands(Lang::De))
+        .language_code(Lang::De.code())
+        .await?;
+
+    Ok(())
+}
+
 pub fn insert_user_registration(chat_id: i64, entry: RegistrationEntry) {
     USER_REGISTRATIONS
         .get()
@@ -63,81 +114,518 @@ pub fn make_mensa_keyboard(
     InlineKeyboardMarkup::new(keyboard)
 }
 
-pub fn make_commands_keyrow() -> KeyboardMarkup {
-    let keyboard = vec![
+pub fn make_commands_keyrow(lang: Lang) -> KeyboardMarkup {
+    let keyboard = lang
+        .texts()
+        .command_keyrow
+        .map(|row| row.iter().map(|command| KeyboardButton::new(*command)));
+    KeyboardMarkup::new(keyboard).resize_keyboard()
+}
+
+pub fn make_week_keyboard(selected: NaiveDate, lang: Lang) -> InlineKeyboardMarkup {
+    let texts = lang.texts();
+    let monday = selected - Duration::days(selected.weekday().num_days_from_monday().into());
+
+    let day_row = texts.weekday_abbr[..5]
+        .iter()
+        .zip(0..)
+        .map(|(day_name, i)| {
+            let day = monday + Duration::days(i);
+            let mut label = format!("{} {}", day_name, day.format("%d."));
+            if day == selected {
+                label = format!("• {} •", label);
+            }
+            InlineKeyboardButton::callback(label, format!("week:{}", day.format("%Y-%m-%d")))
+        });
+
+    let nav_row = [
+        InlineKeyboardButton::callback(
+            texts.week_prev,
+            format!("week:{}", (selected - Duration::days(7)).format("%Y-%m-%d")),
+        ),
+        InlineKeyboardButton::callback(
+            texts.week_next,
+            format!("week:{}", (selected + Duration::days(7)).format("%Y-%m-%d")),
+        ),
+    ];
+
+    InlineKeyboardMarkup::default()
+        .append_row(day_row)
+        .append_row(nav_row)
+}
+
+/// Day shown first by /woche: today, or monday once this week's canteen days are over
+pub fn first_week_day(now: NaiveDateTime) -> NaiveDate {
+    let today = now.date();
+    match today.weekday() {
+        Weekday::Sat => today + Duration::days(2),
+        Weekday::Sun => today + Duration::days(1),
+        Weekday::Fri if now.hour() >= WEEK_ROLLOVER_HOUR => today + Duration::days(3),
+        _ => today,
+    }
+}
+
+/// Diet selection, plus whether unfitting meals are hidden or dimmed
+pub fn make_diet_keyboard(diet: Diet, diet_mode: DietMode, lang: Lang) -> InlineKeyboardMarkup {
+    let texts = lang.texts();
+    let mut keyboard: Vec<Vec<InlineKeyboardButton>> = Diet::ALL
+        .iter()
+        .map(|option| {
+            let label = match *option == diet {
+                true => format!("✅ {}", option.label(lang)),
+                false => option.label(lang).to_string(),
+            };
+            vec![InlineKeyboardButton::callback(
+                label,
+                format!("diet:{}", option.to_db()),
+            )]
+        })
+        .collect();
+
+    keyboard.push(vec![match diet_mode {
+        DietMode::Hide => InlineKeyboardButton::callback(texts.diet_others_hidden, "dietmode:dim"),
+        DietMode::Dim => InlineKeyboardButton::callback(texts.diet_others_dimmed, "dietmode:hide"),
+    }]);
+
+    InlineKeyboardMarkup::new(keyboard)
+}
+
+/// Price tier selection
+pub fn make_price_tier_keyboard(price_tier: PriceTier, lang: Lang) -> InlineKeyboardMarkup {
+    InlineKeyboardMarkup::new(PriceTier::ALL.iter().map(|option| {
+        let label = match *option == price_tier {
+            true => format!("✅ {}", option.label(lang)),
+            false => option.label(lang).to_string(),
+        };
+        vec![InlineKeyboardButton::callback(
+            label,
+            format!("price:{}", option.to_db()),
+        )]
+    }))
+}
+
+/// Allergens to avoid (two per row), plus whether matching meals are marked or hidden
+pub fn make_allergen_keyboard(
+    avoid_allergens: u16,
+    allergen_mode: AllergenMode,
+    lang: Lang,
+) -> InlineKeyboardMarkup {
+    let texts = lang.texts();
+    let mut keyboard: Vec<Vec<InlineKeyboardButton>> = ALLERGENS
+        .chunks(2)
+        .enumerate()
+        .map(|(row, pair)| {
+            pair.iter()
+                .enumerate()
+                .map(|(col, code)| {
+                    let bit = row * 2 + col;
+                    let name = texts.allergen_names[bit];
+                    let label = match avoid_allergens & (1 << bit) != 0 {
+                        true => format!("✅ {}", name),
+                        false => name.to_string(),
+                    };
+                    InlineKeyboardButton::callback(label, format!("avoid:{}", code))
+                })
+                .collect()
+        })
+        .collect();
+
+    keyboard.push(vec![match allergen_mode {
+        AllergenMode::Warn => {
+            InlineKeyboardButton::callback(texts.allergens_marked, "avoidmode:hide")
+        }
+        AllergenMode::Hide => {
+            InlineKeyboardButton::callback(texts.allergens_hidden, "avoidmode:warn")
+        }
+    }]);
+
+    InlineKeyboardMarkup::new(keyboard)
+}
+
+/// One button per watched keyword
+pub fn make_unwatch_keyboard(watches: &[String]) -> InlineKeyboardMarkup {
+    InlineKeyboardMarkup::new(watches.iter().map(|keyword| {
+        vec![InlineKeyboardButton::callback(
+            keyword,
+            format!("unwatch:{}", keyword),
+        )]
+    }))
+}
+
+/// Language selection, each label in its own language
+pub fn make_lang_keyboard(lang: Lang) -> InlineKeyboardMarkup {
+    InlineKeyboardMarkup::new(Lang::ALL.iter().map(|option| {
+        let label = match *option == lang {
+            true => format!("✅ {}", option.label()),
+            false => option.label().to_string(),
+        };
+        vec![InlineKeyboardButton::callback(
+            label,
+            format!("lang:{}", option.code()),
+        )]
+    }))
+}
+
+/// Weekday toggles of the auto send schedule
+pub fn make_weekday_keyboard(schedule: &WeekSchedule, lang: Lang) -> InlineKeyboardMarkup {
+    let day_row = lang
+        .texts()
+        .weekday_abbr
+        .iter()
+        .zip(0..)
+        .map(|(day_name, weekday)| {
+            let label = match schedule.is_enabled(weekday) {
+                true => format!("✅ {}", day_name),
+                false => format!("❌ {}", day_name),
+            };
+            InlineKeyboardButton::callback(label, format!("wday:{}", weekday))
+        });
+
+    InlineKeyboardMarkup::default().append_row(day_row)
+}
+
+/// Send times per enabled weekday, e.g. "Mo 07:00\nDi 06:00"
+pub fn week_schedule_text(registration: &RegistrationEntry) -> String {
+    let texts = registration.settings.lang.texts();
+    if registration.hour.is_none() {
+        return texts.schedule_off.to_string();
+    }
+
+    let days = schedule_days(registration);
+    if days.is_empty() {
+        texts.schedule_no_days.to_string()
+    } else {
+        format!("{}\n{}", texts.schedule_header, days.join("\n"))
+    }
+}
+
+/// "Mo 07:00" for every enabled weekday, empty if auto sending is off
+fn schedule_days(registration: &RegistrationEntry) -> Vec<String> {
+    let (Some(hour), Some(minute)) = (registration.hour, registration.minute) else {
+        return vec![];
+    };
+
+    (0..7)
+        .filter_map(|weekday| {
+            let (hour, minute) = registration.schedule.send_time(weekday, hour, minute)?;
+            Some(format!(
+                "{} {:02}:{:02}",
+                registration.settings.lang.texts().weekday_abbr[weekday as usize],
+                hour,
+                minute
+            ))
+        })
+        .collect()
+}
+
+/// Text and keyboard of the /einstellungen panel
+pub fn settings_page(registration: &RegistrationEntry) -> (String, InlineKeyboardMarkup) {
+    (
+        settings_text(registration),
+        make_settings_keyboard(registration),
+    )
+}
+
+/// Current state of every setting, shown above `make_settings_keyboard`
+pub fn settings_text(registration: &RegistrationEntry) -> String {
+    let settings = &registration.settings;
+    let texts = settings.lang.texts();
+    let on_off = |state: bool| if state { texts.on } else { texts.off };
+
+    let additional_mensen = match registration.additional_mensa_ids.is_empty() {
+        true => texts.none.to_string(),
+        false => registration
+            .additional_mensa_ids
+            .iter()
+            .map(|id| mensa_name(*id))
+            .collect::<Vec<String>>()
+            .join(", "),
+    };
+
+    let auto_send = match registration.hour {
+        None => texts.off.to_string(),
+        Some(_) => match schedule_days(registration) {
+            days if days.is_empty() => texts.no_weekday_selected.to_string(),
+            days => days.join(", "),
+        },
+    };
+
+    let diet = match (settings.diet, settings.diet_mode) {
+        (Diet::All, _) => Diet::All.label(settings.lang).to_string(),
+        (diet, DietMode::Hide) => {
+            format!("{} ({})", diet.label(settings.lang), texts.others_hidden)
+        }
+        (diet, DietMode::Dim) => format!("{} ({})", diet.label(settings.lang), texts.others_dimmed),
+    };
+
+    let avoided: Vec<&str> = texts
+        .allergen_names
+        .iter()
+        .enumerate()
+        .filter(|(bit, _)| settings.avoid_allergens & (1 << bit) != 0)
+        .map(|(_, name)| *name)
+        .collect();
+    let allergen_profile = match (avoided.is_empty(), settings.allergen_mode) {
+        (true, _) => texts.none.to_string(),
+        (false, AllergenMode::Warn) => format!("{} ({})", avoided.join(", "), texts.marked),
+        (false, AllergenMode::Hide) => format!("{} ({})", avoided.join(", "), texts.hidden),
+    };
+
+    let values = [
+        mensa_name(registration.mensa_id),
+        additional_mensen,
+        auto_send,
+        on_off(settings.allergens).to_string(),
+        on_off(settings.senddiff).to_string(),
+        on_off(settings.skip_empty).to_string(),
+        diet,
+        allergen_profile,
+        settings.price_tier.label(settings.lang).to_string(),
+        settings.lang.label().to_string(),
+    ];
+
+    let rows: Vec<String> = texts
+        .settings_rows
+        .iter()
+        .zip(values)
+        .map(|(label, value)| format!("{}: {}", label, value))
+        .collect();
+    format!("{}\n\n{}", texts.settings_title, rows.join("\n"))
+}
+
+/// Buttons of the /einstellungen panel, toggles change in place, the others open a sub page
+fn make_settings_keyboard(registration: &RegistrationEntry) -> InlineKeyboardMarkup {
+    let texts = registration.settings.lang.texts();
+    let toggle = |state: bool, label: &str, data: &str| {
+        InlineKeyboardButton::callback(
+            format!("{} {}", if state { "✅" } else { "❌" }, label),
+            format!("set:{}", data),
+        )
+    };
+    let page = |label: &str, data: &str| {
+        InlineKeyboardButton::callback(label.to_string(), format!("set:{}", data))
+    };
+
+    InlineKeyboardMarkup::new([
         vec![
-            KeyboardButton::new("/heute"),
-            KeyboardButton::new("/morgen"),
-            KeyboardButton::new("/andere"),
-            // KeyboardButton::new("/übermorgen"),
+            page(texts.btn_mensa, "mensa"),
+            page(texts.btn_follow, "follow"),
         ],
         vec![
-            KeyboardButton::new("/mensa"),
-            KeyboardButton::new("/allergene"),
-            KeyboardButton::new("/diff"),
+            toggle(registration.hour.is_some(), texts.btn_auto, "auto"),
+            page(texts.btn_time, "time"),
         ],
-    ];
-    KeyboardMarkup::new(keyboard).resize_keyboard()
+        vec![page(texts.btn_weekdays, "wday")],
+        vec![
+            toggle(
+                registration.settings.allergens,
+                texts.btn_allergens,
+                "allergens",
+            ),
+            toggle(registration.settings.senddiff, texts.btn_diff, "diff"),
+        ],
+        vec![toggle(
+            registration.settings.skip_empty,
+            texts.btn_skip_empty,
+            "skip_empty",
+        )],
+        vec![
+            page(texts.btn_diet, "diet"),
+            page(texts.btn_avoid, "avoid"),
+            page(texts.btn_price, "price"),
+        ],
+        vec![page(texts.btn_lang, "lang")],
+    ])
+}
+
+/// Mensa selection as a sub page of /einstellungen
+fn make_settings_mensa_keyboard(
+    mensen: &BTreeMap<u32, String>,
+    registration: &RegistrationEntry,
+) -> InlineKeyboardMarkup {
+    InlineKeyboardMarkup::new(mensen.iter().map(|(id, name)| {
+        let label = match *id == registration.mensa_id {
+            true => format!("✅ {}", name),
+            false => name.clone(),
+        };
+        [InlineKeyboardButton::callback(
+            label,
+            format!("set_mensa:{}", id),
+        )]
+    }))
+}
+
+/// Adds the button back to the /einstellungen panel
+fn with_settings_back(markup: InlineKeyboardMarkup, lang: Lang) -> InlineKeyboardMarkup {
+    markup.append_row([InlineKeyboardButton::callback(
+        lang.texts().btn_back,
+        SETTINGS_BACK,
+    )])
 }
 
+/// Keyboards opened from /einstellungen keep their back button when they update themselves
+fn keep_settings_back(
+    message: &MaybeInaccessibleMessage,
+    markup: InlineKeyboardMarkup,
+    lang: Lang,
+) -> InlineKeyboardMarkup {
+    let opened_from_settings = message
+        .regular_message()
+        .and_then(|msg| msg.reply_markup())
+        .is_some_and(|keyboard| {
+            keyboard
+                .inline_keyboard
+                .iter()
+                .flatten()
+                .any(|button| match &button.kind {
+                    InlineKeyboardButtonKind::CallbackData(data) => data == SETTINGS_BACK,
+                    _ => false,
+                })
+        });
+
+    match opened_from_settings {
+        true => with_settings_back(markup, lang),
+        false => markup,
+    }
+}
+
+/// Toggle list of mensen to follow in addition to the main one (📌)
+pub fn make_follow_keyboard(
+    mensen: &BTreeMap<u32, String>,
+    registration: &RegistrationEntry,
+) -> InlineKeyboardMarkup {
+    let keyboard = mensen.iter().map(|(id, name)| {
+        let label = if *id == registration.mensa_id {
+            format!("📌 {}", name)
+        } else if registration.additional_mensa_ids.contains(id) {
+            format!("✅ {}", name)
+        } else {
+            name.clone()
+        };
+        [InlineKeyboardButton::callback(
+            label,
+            format!("m_follow:{}", id),
+        )]
+    });
+
+    InlineKeyboardMarkup::new(keyboard)
+}
+
+/// Plan rendered with the chat's settings, `lang` is only used for unregistered chats
 pub async fn build_meal_message_dispatcher(
     chat_id: i64,
-    days_forward: i64,
-    mensa_location: u32,
+    date: NaiveDate,
+    mensa_ids: &[u32],
+    lang: Lang,
 ) -> String {
-    let wants_allergens = get_user_registration(chat_id)
-        .map(|reg| reg.allergens)
-        .unwrap_or_default();
-    match BACKEND.get().unwrap() {
-        Backend::MensiMates => {
-            mm_build_meal_msg(days_forward, mensa_location, wants_allergens).await
-        }
-        Backend::StuWe => stuwe_build_meal_msg(days_forward, mensa_location, wants_allergens).await,
-    }
+    let options = get_user_registration(chat_id)
+        .map(|reg| MealRenderOptions::from(&reg))
+        .unwrap_or(MealRenderOptions {
+            lang,
+            ..Default::default()
+        });
+    build_meal_msg(date, mensa_ids, &options).await
 }
 
-pub async fn load_job(bot: Bot, sched: &JobScheduler, task: JobHandlerTask) -> Option<Uuid> {
+/// Adds one job per distinct send time of the chat's schedule, none if auto send is off
+pub async fn load_job(
+    bot: Bot,
+    sched: &JobScheduler,
+    task: JobHandlerTask,
+    schedule: &WeekSchedule,
+) -> Vec<Uuid> {
+    let mut uuids = Vec::new();
+
     // return if no time is set
-    task.hour?;
+    let (Some(hour), Some(minute)) = (task.hour, task.minute) else {
+        return uuids;
+    };
 
-    // convert de time to utc
-    let de_time = chrono::Local::now()
-        .with_hour(task.hour.unwrap())
-        .unwrap()
-        .with_minute(task.minute.unwrap())
-        .unwrap()
-        .with_second(0)
-        .unwrap();
-    let utc_time = de_time.naive_utc();
+    // cron runs in utc, so there is one job per utc offset of the local zone (CET/CEST)
+    let clock = LocalClock;
+    let jobs = send_jobs(&clock, &schedule.delivery_times(hour, minute));
 
-    let job = Job::new_async(
-        format!(
-            "0 {} {} * * Mon,Tue,Wed,Thu,Fri",
-            utc_time.minute(),
-            utc_time.hour()
-        )
-        .as_str(),
-        move |_uuid, mut _l| {
+    for (cron_schedule, offset) in jobs {
+        let bot = bot.clone();
+        let sched_handle = sched.clone();
+        let task = task.clone();
+        let job = Job::new_async(cron_schedule.as_str(), move |_uuid, mut _l| {
             let bot = bot.clone();
+            let sched = sched_handle.clone();
 
             Box::pin(async move {
-                bot.send_message(
-                    ChatId(task.chat_id.unwrap()),
-                    build_meal_message_dispatcher(task.chat_id.unwrap(), 0, task.mensa_id.unwrap())
+                // the job of the other offset sends while DST is (not) in effect
+                let Some(today) = active_send_date(&clock, offset) else {
+                    return;
+                };
+
+                if let Some(holiday) = saxony_holiday(today) {
+                    log::debug!("Skipping send to {}: {}", task.chat_id.unwrap(), holiday);
+                    return;
+                }
+
+                // followed mensen may have changed since the job was created
+                let registration = get_user_registration(task.chat_id.unwrap());
+                let mensa_ids = registration
+                    .as_ref()
+                    .map(|reg| reg.mensa_ids())
+                    .unwrap_or_else(|| vec![task.mensa_id.unwrap()]);
+                let lang = registration
+                    .as_ref()
+                    .map(|reg| reg.settings.lang)
+                    .unwrap_or_default();
+
+                if registration.is_some_and(|reg| reg.settings.skip_empty)
+                    && !any_meals(&mensa_ids, resolve_days_forward(0)).await
+                {
+                    log::debug!("Skipping send to {}: no meals", task.chat_id.unwrap());
+                    return;
+                }
+
+                let sent = bot
+                    .send_message(
+                        ChatId(task.chat_id.unwrap()),
+                        build_meal_message_dispatcher(
+                            task.chat_id.unwrap(),
+                            resolve_days_forward(0),
+                            &mensa_ids,
+                            lang,
+                        )
                         .await,
-                )
-                .parse_mode(ParseMode::MarkdownV2)
-                .await
-                .unwrap();
+                    )
+                    .parse_mode(ParseMode::MarkdownV2)
+                    .await;
+                if let Err(e) = sent {
+                    handle_send_error(task.chat_id.unwrap(), e, &sched).await;
+                }
             })
-        },
-    )
-    .unwrap();
+        })
+        .unwrap();
 
-    let uuid = job.guid();
-    sched.add(job).await.unwrap();
+        uuids.push(job.guid());
+        sched.add(job).await.unwrap();
+    }
 
-    Some(uuid)
+    uuids
+}
+
+/// The bot was blocked, or removed from a group
+pub async fn my_chat_member_handler(
+    upd: ChatMemberUpdated,
+    jobhandler_task_tx: broadcast::Sender<JobHandlerTask>,
+) -> HandlerResult {
+    if !upd.new_chat_member.is_present() {
+        jobhandler_task_tx
+            .send(
+                RemoveChatTask {
+                    chat_id: upd.chat.id.0,
+                }
+                .into(),
+            )
+            .unwrap();
+    }
+
+    Ok(())
 }
 
 pub async fn callback_handler(
@@ -145,8 +633,25 @@ pub async fn callback_handler(
     q: CallbackQuery,
     mensen: BTreeMap<u32, String>,
     jobhandler_task_tx: broadcast::Sender<JobHandlerTask>,
+    dialogue: DialogueType,
+    lang: Lang,
 ) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let texts = lang.texts();
     if let Some(q_data) = q.data {
+        let (cmd, arg) = q_data.split_once(':').unwrap();
+
+        // everything but showing meals changes the chat's settings
+        let read_only = matches!(cmd, "m_disp" | "day" | "week");
+        if let Some(message) = &q.message {
+            if !read_only && !is_chat_admin(&bot, message.chat(), q.from.id).await {
+                bot.answer_callback_query(q.id)
+                    .text(texts.admin_only)
+                    .show_alert(true)
+                    .await?;
+                return Ok(());
+            }
+        }
+
         // acknowledge callback query to remove the loading alert
         bot.answer_callback_query(q.id).await?;
 
@@ -154,14 +659,13 @@ pub async fn callback_handler(
             let id = message.id();
             let chat = message.chat();
 
-            let (cmd, arg) = q_data.split_once(':').unwrap();
             match cmd {
                 "m_upd" => {
                     // replace mensa selection message with selected mensa
                     bot.edit_message_text(
                         chat.id,
                         id,
-                        format!("Gewählte Mensa: {}", markdown::bold(arg)),
+                        (texts.selected_mensa)(&markdown::bold(arg)),
                     )
                     .parse_mode(ParseMode::MarkdownV2)
                     .await
@@ -169,8 +673,9 @@ pub async fn callback_handler(
 
                     let text = build_meal_message_dispatcher(
                         chat.id.0,
-                        0,
-                        *mensen.iter().find(|(_, v)| v.as_str() == arg).unwrap().0,
+                        resolve_days_forward(0),
+                        &[*mensen.iter().find(|(_, v)| v.as_str() == arg).unwrap().0],
+                        lang,
                     )
                     .await;
 
@@ -186,7 +691,7 @@ pub async fn callback_handler(
                     }
                     .into();
 
-                    update_db_row(&task).unwrap();
+                    update_db_row(&task).await.unwrap();
                     jobhandler_task_tx.send(task).unwrap();
                 }
                 "m_disp" => {
@@ -200,8 +705,9 @@ pub async fn callback_handler(
                         chat.id,
                         build_meal_message_dispatcher(
                             chat.id.0,
-                            0,
-                            *mensen.iter().find(|(_, v)| v.as_str() == arg).unwrap().0,
+                            resolve_days_forward(0),
+                            &[*mensen.iter().find(|(_, v)| v.as_str() == arg).unwrap().0],
+                            lang,
                         )
                         .await,
                     )
@@ -210,29 +716,30 @@ pub async fn callback_handler(
                 }
                 "m_regist" => {
                     // replace mensa selection message with list of commands
-                    bot.edit_message_text(chat.id, id, Command::descriptions().to_string())
-                        .await?;
+                    bot.edit_message_text(chat.id, id, help_text(lang)).await?;
 
                     let task = RegisterTask {
                         chat_id: chat.id.0,
                         mensa_id: *mensen.iter().find(|(_, v)| v.as_str() == arg).unwrap().0,
                         hour: 6,
                         minute: 0,
+                        lang,
                     }
                     .into();
 
-                    update_db_row(&task).unwrap();
+                    update_db_row(&task).await.unwrap();
                     jobhandler_task_tx.send(task).unwrap();
 
-                    bot
-                        .send_message(chat.id, format!("Plan der {} wird ab jetzt automatisch an Wochentagen *06:00 Uhr* gesendet\\.\n\nÄndern mit\n/mensa oder /uhrzeit", markdown::bold(arg)))
+                    bot.send_message(chat.id, (texts.registered)(&markdown::bold(arg)))
                         .parse_mode(ParseMode::MarkdownV2)
-                        .reply_markup(make_commands_keyrow()).await?;
+                        .reply_markup(make_commands_keyrow(lang))
+                        .await?;
 
                     let text = build_meal_message_dispatcher(
                         chat.id.0,
-                        0,
-                        *mensen.iter().find(|(_, v)| v.as_str() == arg).unwrap().0,
+                        resolve_days_forward(0),
+                        &[*mensen.iter().find(|(_, v)| v.as_str() == arg).unwrap().0],
+                        lang,
                     )
                     .await;
 
@@ -240,30 +747,368 @@ pub async fn callback_handler(
                         .parse_mode(ParseMode::MarkdownV2)
                         .await?;
                 }
+                "m_follow" => {
+                    if let Some(mut registration) = get_user_registration(chat.id.0) {
+                        let mensa_id = arg.parse::<u32>().unwrap();
+
+                        // the main mensa is changed with /mensa, not here
+                        if mensa_id != registration.mensa_id {
+                            let follow = !registration.additional_mensa_ids.contains(&mensa_id);
+                            set_additional_mensa(chat.id.0, mensa_id, follow).await?;
+
+                            if follow {
+                                registration.additional_mensa_ids.push(mensa_id);
+                                registration.additional_mensa_ids.sort_unstable();
+                            } else {
+                                registration
+                                    .additional_mensa_ids
+                                    .retain(|id| *id != mensa_id);
+                            }
+
+                            bot.edit_message_reply_markup(chat.id, id)
+                                .reply_markup(keep_settings_back(
+                                    &message,
+                                    make_follow_keyboard(&mensen, &registration),
+                                    lang,
+                                ))
+                                .await?;
+                            insert_user_registration(chat.id.0, registration);
+                        }
+                    } else {
+                        bot.send_message(chat.id, texts.no_db).await?;
+                    }
+                }
+                "price" => {
+                    if let Some(mut registration) = get_user_registration(chat.id.0) {
+                        registration.settings.price_tier = PriceTier::from_db(arg.parse().unwrap());
+                        save_user_settings(chat.id.0, &registration.settings).await?;
+
+                        match bot
+                            .edit_message_reply_markup(chat.id, id)
+                            .reply_markup(keep_settings_back(
+                                &message,
+                                make_price_tier_keyboard(registration.settings.price_tier, lang),
+                                lang,
+                            ))
+                            .await
+                        {
+                            Ok(_) | Err(RequestError::Api(ApiError::MessageNotModified)) => {}
+                            Err(e) => return Err(e.into()),
+                        }
+
+                        insert_user_registration(chat.id.0, registration);
+                    } else {
+                        bot.send_message(chat.id, texts.no_db).await?;
+                    }
+                }
+                "diet" | "dietmode" => {
+                    if let Some(mut registration) = get_user_registration(chat.id.0) {
+                        match (cmd, arg) {
+                            ("diet", _) => {
+                                registration.settings.diet = Diet::from_db(arg.parse().unwrap())
+                            }
+                            (_, "dim") => registration.settings.diet_mode = DietMode::Dim,
+                            _ => registration.settings.diet_mode = DietMode::Hide,
+                        }
+                        save_user_settings(chat.id.0, &registration.settings).await?;
+
+                        // unchanged selection is not an error
+                        match bot
+                            .edit_message_reply_markup(chat.id, id)
+                            .reply_markup(keep_settings_back(
+                                &message,
+                                make_diet_keyboard(
+                                    registration.settings.diet,
+                                    registration.settings.diet_mode,
+                                    lang,
+                                ),
+                                lang,
+                            ))
+                            .await
+                        {
+                            Ok(_) | Err(RequestError::Api(ApiError::MessageNotModified)) => {}
+                            Err(e) => return Err(e.into()),
+                        }
+
+                        insert_user_registration(chat.id.0, registration);
+                    } else {
+                        bot.send_message(chat.id, texts.no_db).await?;
+                    }
+                }
+                "avoid" | "avoidmode" => {
+                    if let Some(mut registration) = get_user_registration(chat.id.0) {
+                        match (cmd, arg) {
+                            ("avoid", _) => {
+                                if let Some(bit) = arg.chars().next().and_then(allergen_bit) {
+                                    registration.settings.avoid_allergens ^= bit;
+                                }
+                            }
+                            (_, "hide") => registration.settings.allergen_mode = AllergenMode::Hide,
+                            _ => registration.settings.allergen_mode = AllergenMode::Warn,
+                        }
+                        save_user_settings(chat.id.0, &registration.settings).await?;
+
+                        match bot
+                            .edit_message_reply_markup(chat.id, id)
+                            .reply_markup(keep_settings_back(
+                                &message,
+                                make_allergen_keyboard(
+                                    registration.settings.avoid_allergens,
+                                    registration.settings.allergen_mode,
+                                    lang,
+                                ),
+                                lang,
+                            ))
+                            .await
+                        {
+                            Ok(_) | Err(RequestError::Api(ApiError::MessageNotModified)) => {}
+                            Err(e) => return Err(e.into()),
+                        }
+
+                        insert_user_registration(chat.id.0, registration);
+                    } else {
+                        bot.send_message(chat.id, texts.no_db).await?;
+                    }
+                }
+                "set" => {
+                    let Some(mut registration) = get_user_registration(chat.id.0) else {
+                        bot.send_message(chat.id, texts.no_db).await?;
+                        return Ok(());
+                    };
+
+                    let sub_page = |text: &str, keyboard| {
+                        Some((text.to_string(), with_settings_back(keyboard, lang)))
+                    };
+
+                    // (text, keyboard) to show, None keeps the message
+                    let page = match arg {
+                        "mensa" => sub_page(
+                            texts.choose_mensa,
+                            make_settings_mensa_keyboard(&mensen, &registration),
+                        ),
+                        "follow" => sub_page(
+                            texts.follow_page,
+                            make_follow_keyboard(&mensen, &registration),
+                        ),
+                        "wday" => sub_page(
+                            &week_schedule_text(&registration),
+                            make_weekday_keyboard(&registration.schedule, lang),
+                        ),
+                        "diet" => sub_page(
+                            texts.choose_diet,
+                            make_diet_keyboard(
+                                registration.settings.diet,
+                                registration.settings.diet_mode,
+                                lang,
+                            ),
+                        ),
+                        "avoid" => sub_page(
+                            texts.choose_allergens,
+                            make_allergen_keyboard(
+                                registration.settings.avoid_allergens,
+                                registration.settings.allergen_mode,
+                                lang,
+                            ),
+                        ),
+                        "price" => sub_page(
+                            texts.choose_price_tier,
+                            make_price_tier_keyboard(registration.settings.price_tier, lang),
+                        ),
+                        "lang" => sub_page(texts.choose_lang, make_lang_keyboard(lang)),
+                        "time" => {
+                            bot.send_message(chat.id, texts.reply_with_time).await?;
+                            dialogue.update(DialogueState::AwaitTimeReply).await?;
+                            None
+                        }
+                        "allergens" => {
+                            registration.settings.allergens = !registration.settings.allergens;
+                            save_user_settings(chat.id.0, &registration.settings).await?;
+                            insert_user_registration(chat.id.0, registration.clone());
+                            Some(settings_page(&registration))
+                        }
+                        "diff" => {
+                            registration.settings.senddiff = !registration.settings.senddiff;
+                            save_user_settings(chat.id.0, &registration.settings).await?;
+                            insert_user_registration(chat.id.0, registration.clone());
+                            Some(settings_page(&registration))
+                        }
+                        "skip_empty" => {
+                            registration.settings.skip_empty = !registration.settings.skip_empty;
+                            save_user_settings(chat.id.0, &registration.settings).await?;
+                            insert_user_registration(chat.id.0, registration.clone());
+                            Some(settings_page(&registration))
+                        }
+                        "auto" => {
+                            let task = match registration.hour {
+                                Some(_) => UnregisterTask { chat_id: chat.id.0 }.into(),
+                                None => UpdateRegistrationTask {
+                                    chat_id: chat.id.0,
+                                    mensa_id: None,
+                                    hour: Some(6),
+                                    minute: Some(0),
+                                }
+                                .into(),
+                            };
+                            jobhandler_task_tx.send(task).unwrap();
+
+                            // the job handler updates the registration, show the result already
+                            (registration.hour, registration.minute) = match registration.hour {
+                                Some(_) => (None, None),
+                                None => (Some(6), Some(0)),
+                            };
+                            Some(settings_page(&registration))
+                        }
+                        // "main", back from a sub page
+                        _ => Some(settings_page(&registration)),
+                    };
+
+                    if let Some((text, keyboard)) = page {
+                        bot.edit_message_text(chat.id, id, text)
+                            .reply_markup(keyboard)
+                            .await?;
+                    }
+                }
+                "set_mensa" => {
+                    if let Some(mut registration) = get_user_registration(chat.id.0) {
+                        let mensa_id: u32 = arg.parse().unwrap();
+
+                        let task = UpdateRegistrationTask {
+                            chat_id: chat.id.0,
+                            mensa_id: Some(mensa_id),
+                            hour: None,
+                            minute: None,
+                        }
+                        .into();
+                        update_db_row(&task).await.unwrap();
+                        jobhandler_task_tx.send(task).unwrap();
+
+                        // the job handler updates the registration, show the result already
+                        registration.mensa_id = mensa_id;
+                        registration
+                            .additional_mensa_ids
+                            .retain(|id| *id != mensa_id);
+
+                        let (text, keyboard) = settings_page(&registration);
+                        bot.edit_message_text(chat.id, id, text)
+                            .reply_markup(keyboard)
+                            .await?;
+                    } else {
+                        bot.send_message(chat.id, texts.no_db).await?;
+                    }
+                }
+                "lang" => {
+                    if let Some(mut registration) = get_user_registration(chat.id.0) {
+                        let lang = Lang::from_db(arg);
+                        registration.settings.lang = lang;
+                        save_user_settings(chat.id.0, &registration.settings).await?;
+                        insert_user_registration(chat.id.0, registration);
+
+                        // the command menu of this chat follows its language
+                        bot.set_my_commands(bot_commands(lang))
+                            .scope(BotCommandScope::Chat {
+                                chat_id: Recipient::Id(chat.id),
+                            })
+                            .await?;
+
+                        match bot
+                            .edit_message_text(chat.id, id, lang.texts().choose_lang)
+                            .reply_markup(keep_settings_back(
+                                &message,
+                                make_lang_keyboard(lang),
+                                lang,
+                            ))
+                            .await
+                        {
+                            Ok(_) | Err(RequestError::Api(ApiError::MessageNotModified)) => {}
+                            Err(e) => return Err(e.into()),
+                        }
+                    } else {
+                        bot.send_message(chat.id, texts.no_db).await?;
+                    }
+                }
+                "unwatch" => {
+                    remove_watch(chat.id.0, arg).await?;
+                    let watches = get_user_watches(chat.id.0).await?;
+
+                    if watches.is_empty() {
+                        bot.edit_message_text(chat.id, id, texts.watch_none_left)
+                            .await?;
+                    } else {
+                        bot.edit_message_reply_markup(chat.id, id)
+                            .reply_markup(make_unwatch_keyboard(&watches))
+                            .await?;
+                    }
+                }
+                "wday" => {
+                    if let Some(mut registration) = get_user_registration(chat.id.0) {
+                        registration.schedule.toggle(arg.parse().unwrap());
+                        save_user_schedule(chat.id.0, &registration.schedule).await?;
+
+                        bot.edit_message_text(chat.id, id, week_schedule_text(&registration))
+                            .reply_markup(keep_settings_back(
+                                &message,
+                                make_weekday_keyboard(&registration.schedule, lang),
+                                lang,
+                            ))
+                            .await?;
+
+                        insert_user_registration(chat.id.0, registration);
+                        jobhandler_task_tx
+                            .send(UpdateScheduleTask { chat_id: chat.id.0 }.into())
+                            .unwrap();
+                    } else {
+                        bot.send_message(chat.id, texts.no_db).await?;
+                    }
+                }
                 "day" => {
                     if let Some(registration) = get_user_registration(chat.id.0) {
                         // start building message
                         let now = Instant::now();
 
                         let days_forward = arg.parse::<i64>().unwrap();
-                        let day_str = ["Heute", "Morgen", "Übermorgen"]
-                            [usize::try_from(days_forward).unwrap()];
 
                         let text = build_meal_message_dispatcher(
                             chat.id.0,
-                            days_forward,
-                            registration.mensa_id,
+                            resolve_days_forward(days_forward),
+                            &registration.mensa_ids(),
+                            lang,
                         )
                         .await;
-                        log::debug!("Build {} msg: {:.2?}", day_str, now.elapsed());
+                        log::debug!("Build +{}d msg: {:.2?}", days_forward, now.elapsed());
                         let now = Instant::now();
 
                         bot.send_message(chat.id, text)
                             .parse_mode(ParseMode::MarkdownV2)
                             .await?;
-                        log::debug!("Send {} msg: {:.2?}", day_str, now.elapsed());
+                        log::debug!("Send +{}d msg: {:.2?}", days_forward, now.elapsed());
                     } else {
-                        bot.send_message(chat.id, NO_DB_MSG).await?;
+                        bot.send_message(chat.id, texts.no_db).await?;
+                    }
+                }
+                "week" => {
+                    if let Some(registration) = get_user_registration(chat.id.0) {
+                        let date = NaiveDate::parse_from_str(arg, "%Y-%m-%d").unwrap();
+
+                        let text = build_meal_message_dispatcher(
+                            chat.id.0,
+                            date,
+                            &registration.mensa_ids(),
+                            lang,
+                        )
+                        .await;
+
+                        // page through the week by editing the same message
+                        match bot
+                            .edit_message_text(chat.id, id, text)
+                            .parse_mode(ParseMode::MarkdownV2)
+                            .reply_markup(make_week_keyboard(date, lang))
+                            .await
+                        {
+                            Ok(_) | Err(RequestError::Api(ApiError::MessageNotModified)) => {}
+                            Err(e) => return Err(e.into()),
+                        }
+                    } else {
+                        bot.send_message(chat.id, texts.no_db).await?;
                     }
                 }
                 _ => panic!("Unknown callback query command: {}", cmd),
@@ -273,3 +1118,156 @@ pub async fn callback_handler(
 
     Ok(())
 }
+
+/// Splits an inline query like "dittrich morgen" into matching mensa IDs and the requested date.
+/// The date is taken from the last one to three words, if they form one (default: today).
+pub fn parse_inline_query(
+    query: &str,
+    mensen: &BTreeMap<u32, String>,
+    today: NaiveDate,
+) -> (Vec<u32>, Option<NaiveDate>) {
+    let words: Vec<&str> = query.split_whitespace().collect();
+
+    let (mensa_words, date) = (1..=words.len().min(3))
+        .rev()
+        .find_map(|date_len| {
+            let split = words.len() - date_len;
+            parse_german_date(&words[split..].join(" "), today).map(|date| (&words[..split], date))
+        })
+        .map_or((&words[..], None), |(mensa_words, date)| {
+            (mensa_words, Some(date))
+        });
+
+    let mensa_ids = mensen
+        .iter()
+        .filter(|(_, name)| {
+            let name = name.to_lowercase();
+            mensa_words
+                .iter()
+                .all(|word| name.contains(&word.to_lowercase()))
+        })
+        .map(|(id, _)| *id)
+        .collect();
+
+    (mensa_ids, date)
+}
+
+pub async fn inline_query_handler(
+    bot: Bot,
+    q: InlineQuery,
+    mensen: BTreeMap<u32, String>,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    // the user ID doubles as chat ID of the private chat, used for allergen settings
+    let user_id = q.from.id.0 as i64;
+    let lang = chat_lang(ChatId(user_id), Some(&q.from));
+    let (mut mensa_ids, date) =
+        parse_inline_query(&q.query, &mensen, chrono::Local::now().date_naive());
+    let date = date.unwrap_or_else(|| resolve_days_forward(0));
+
+    // registered mensa first, so an empty query shows it at the top
+    if let Some(registration) = get_user_registration(user_id) {
+        if let Some(pos) = mensa_ids.iter().position(|id| *id == registration.mensa_id) {
+            let mensa_id = mensa_ids.remove(pos);
+            mensa_ids.insert(0, mensa_id);
+        }
+    }
+    mensa_ids.truncate(INLINE_MAX_RESULTS);
+
+    let mut results = Vec::new();
+    for mensa_id in mensa_ids {
+        let text = build_meal_message_dispatcher(user_id, date, &[mensa_id], lang).await;
+        results.push(InlineQueryResult::Article(
+            InlineQueryResultArticle::new(
+                format!("{}_{}", mensa_id, date.format("%Y-%m-%d")),
+                &mensen[&mensa_id],
+                InputMessageContent::Text(
+                    InputMessageContentText::new(text).parse_mode(ParseMode::MarkdownV2),
+                ),
+            )
+            .description(date_fmt(date, lang)),
+        ));
+    }
+
+    bot.answer_inline_query(q.id, results)
+        .cache_time(60)
+        .is_personal(true)
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mensen() -> BTreeMap<u32, String> {
+        BTreeMap::from([
+            (106, "Mensa Academica".to_string()),
+            (111, "Mensa am Park".to_string()),
+            (153, "Cafeteria Dittrichring".to_string()),
+        ])
+    }
+
+    // a wednesday
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 10, 22).unwrap()
+    }
+
+    fn date(month: u32, day: u32) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(2025, month, day)
+    }
+
+    #[test]
+    fn trailing_date_words() {
+        let mensen = mensen();
+        assert_eq!(
+            parse_inline_query("dittrich morgen", &mensen, today()),
+            (vec![153], date(10, 23))
+        );
+        assert_eq!(
+            parse_inline_query("dittrich nächsten montag", &mensen, today()),
+            (vec![153], date(10, 27))
+        );
+        assert_eq!(
+            parse_inline_query("mensa park in 3 Tagen", &mensen, today()),
+            (vec![111], date(10, 25))
+        );
+    }
+
+    #[test]
+    fn only_a_date() {
+        assert_eq!(
+            parse_inline_query("24.10.", &mensen(), today()),
+            (vec![106, 111, 153], date(10, 24))
+        );
+    }
+
+    #[test]
+    fn empty_query_matches_every_mensa() {
+        assert_eq!(
+            parse_inline_query("", &mensen(), today()),
+            (vec![106, 111, 153], None)
+        );
+        assert_eq!(
+            parse_inline_query("  ", &mensen(), today()),
+            (vec![106, 111, 153], None)
+        );
+    }
+
+    #[test]
+    fn name_fragments() {
+        let mensen = mensen();
+        assert_eq!(
+            parse_inline_query("Park", &mensen, today()),
+            (vec![111], None)
+        );
+        assert_eq!(
+            parse_inline_query("mensa", &mensen, today()),
+            (vec![106, 111], None)
+        );
+        assert_eq!(
+            parse_inline_query("mensa park xyz", &mensen, today()),
+            (vec![], None)
+        );
+    }
+}
@@ -1,14 +1,10 @@
-use anyhow::Result;
-use chrono::Timelike;
-use futures_util::TryStreamExt;
-use reqwest::Client;
-use reqwest_websocket::{Message, RequestBuilderExt};
+use chrono::{Datelike, Timelike};
 use std::{collections::BTreeMap, env, time::Duration};
 use teloxide::{
     payloads::SendMessageSetters,
     requests::Requester,
     types::{ChatId, ParseMode},
-    Bot,
+    ApiError, Bot, RequestError,
 };
 use tokio::{sync::broadcast::Sender, time::sleep};
 use tokio_cron_scheduler::{Job, JobScheduler};
@@ -18,18 +14,30 @@ use crate::{
         compare_campusdual_grades, compare_campusdual_signup_options, get_campusdual_data,
         save_campusdual_grades, save_campusdual_signup_options,
     },
-    constants::{API_URL, BACKEND, CD_DATA, NO_DB_MSG, USER_REGISTRATIONS},
-    data_backend::stuwe_parser::{stuwe_build_diff_msg, stuwe_build_meal_msg},
+    constants::{BACKEND, CD_DATA, USER_REGISTRATIONS},
+    data_backend::{
+        build_meal_msg, mensa_name, resolve_days_forward, stuwe_parser::stuwe_build_diff_msg,
+        MealRenderOptions,
+    },
     data_types::{
-        Backend, BroadcastUpdateTask, JobHandlerTask, RegistrationEntry, UpdateRegistrationTask,
+        HandlerResult, JobHandlerTask, RegistrationEntry, RemoveChatTask, UpdateRegistrationTask,
+        UserSettings,
     },
     db_operations::{
-        get_all_user_registrations_db, get_user_allergen_state, get_user_senddiff_state,
-        init_db_record, task_db_kill_auto, update_db_row,
+        delete_chat, get_all_user_registrations_db, init_db_record, invalidate_cached_meals,
+        save_user_schedule, set_additional_mensa, task_db_kill_auto, update_db_row,
     },
-    shared_main::{get_user_registration, insert_user_registration, load_job},
+    meal_watches::scan_meal_watches,
+    shared_main::{chat_lang, get_user_registration, insert_user_registration, load_job},
 };
 
+const GONE_CHAT_ERRORS: [&str; 4] = [
+    "bot was kicked from the group chat",
+    "bot was kicked from the channel chat",
+    "bot is not a member of the group chat",
+    "bot is not a member of the channel chat",
+];
+
 pub async fn handle_add_registration_task(
     bot: &Bot,
     job_handler_task: JobHandlerTask,
@@ -40,26 +48,55 @@ pub async fn handle_add_registration_task(
         &job_handler_task.chat_id.unwrap(),
         &job_handler_task.mensa_id.unwrap()
     );
-    // create or update row in db
-    init_db_record(&job_handler_task).unwrap();
     let registration = get_user_registration(job_handler_task.chat_id.unwrap());
-    if let Some(uuid) = registration.and_then(|reg| reg.job_uuid) {
-        sched.context.job_delete_tx.send(uuid).unwrap();
+    let settings = registration
+        .as_ref()
+        .map(|reg| reg.settings.clone())
+        .unwrap_or_else(|| UserSettings {
+            lang: job_handler_task.lang.unwrap_or_default(),
+            ..Default::default()
+        });
+
+    // create or update row in db, keeping the settings of a re-registration
+    init_db_record(&job_handler_task, &settings).await.unwrap();
+    for uuid in registration.iter().flat_map(|reg| &reg.job_uuids) {
+        sched.context.job_delete_tx.send(*uuid).unwrap();
     }
 
-    // get uuid (here guaranteed to be Some() since default is registration with job)
-    let new_uuid = load_job(bot.clone(), sched, job_handler_task.clone()).await;
+    // the db row was replaced, so the schedule has to be written again
+    let schedule = registration
+        .as_ref()
+        .map(|reg| reg.schedule.clone())
+        .unwrap_or_default();
+    save_user_schedule(job_handler_task.chat_id.unwrap(), &schedule)
+        .await
+        .unwrap();
+
+    let new_uuids = load_job(bot.clone(), sched, job_handler_task.clone(), &schedule).await;
+
+    let mensa_id = job_handler_task.mensa_id.unwrap();
+    let mut additional_mensa_ids = registration
+        .as_ref()
+        .map(|reg| reg.additional_mensa_ids.clone())
+        .unwrap_or_default();
+    if additional_mensa_ids.contains(&mensa_id) {
+        set_additional_mensa(job_handler_task.chat_id.unwrap(), mensa_id, false)
+            .await
+            .unwrap();
+        additional_mensa_ids.retain(|id| *id != mensa_id);
+    }
 
     // insert new job uuid
     insert_user_registration(
         job_handler_task.chat_id.unwrap(),
         RegistrationEntry {
-            job_uuid: new_uuid,
-            mensa_id: job_handler_task.mensa_id.unwrap(),
+            job_uuids: new_uuids,
+            mensa_id,
+            additional_mensa_ids,
             hour: job_handler_task.hour,
             minute: job_handler_task.minute,
-            allergens: registration.map(|reg| reg.allergens).unwrap_or(true),
-            senddiff: registration.map(|reg| reg.senddiff).unwrap_or(true),
+            settings,
+            schedule,
         },
     );
 }
@@ -72,12 +109,12 @@ pub async fn handle_update_registration_task(
     if let Some(mensa) = job_handler_task.mensa_id {
         log::info!("{} 📌 to {}", job_handler_task.chat_id.unwrap(), mensa);
     }
-    if job_handler_task.hour.is_some() {
+    if let (Some(hour), Some(minute)) = (job_handler_task.hour, job_handler_task.minute) {
         log::info!(
             "{} changed 🕘: {:02}:{:02}",
             job_handler_task.chat_id.unwrap(),
-            job_handler_task.hour.unwrap(),
-            job_handler_task.minute.unwrap()
+            hour,
+            minute
         );
     }
 
@@ -87,6 +124,15 @@ pub async fn handle_update_registration_task(
         let hour = job_handler_task.hour.or(registration.hour);
         let minute = job_handler_task.minute.or(registration.minute);
 
+        // new main mensa is no longer an additional one
+        let mut additional_mensa_ids = registration.additional_mensa_ids.clone();
+        if additional_mensa_ids.contains(&mensa_id) {
+            set_additional_mensa(job_handler_task.chat_id.unwrap(), mensa_id, false)
+                .await
+                .unwrap();
+            additional_mensa_ids.retain(|id| *id != mensa_id);
+        }
+
         let new_job_task = UpdateRegistrationTask {
             chat_id: job_handler_task.chat_id.unwrap(),
             mensa_id: Some(mensa_id),
@@ -94,43 +140,48 @@ pub async fn handle_update_registration_task(
             minute,
         };
 
-        let new_uuid =
-            // new time was passed -> unload old job, load new
+        let new_uuids =
+            // new time was passed -> unload old jobs, load new
             if job_handler_task.hour.is_some() || job_handler_task.minute.is_some() || job_handler_task.mensa_id.is_some() {
-                if let Some(uuid) = registration.job_uuid {
-                    // unload old job if exists
-                    sched.context.job_delete_tx.send(uuid).unwrap();
+                for uuid in &registration.job_uuids {
+                    sched.context.job_delete_tx.send(*uuid).unwrap();
                 }
-                // load new job, return uuid
+                // load new jobs, return uuids
                 load_job(
                     bot.clone(),
                     sched,
                     new_job_task.into(),
+                    &registration.schedule,
                 ).await
             } else {
-                // no new time was set -> return old job uuid
-                registration.job_uuid
+                // no new time was set -> return old job uuids
+                registration.job_uuids.clone()
             };
 
         insert_user_registration(
             job_handler_task.chat_id.unwrap(),
             RegistrationEntry {
-                job_uuid: new_uuid,
+                job_uuids: new_uuids,
                 mensa_id,
+                additional_mensa_ids,
                 hour,
                 minute,
-                allergens: registration.allergens,
-                senddiff: registration.senddiff,
+                settings: registration.settings,
+                schedule: registration.schedule,
             },
         );
 
         // update any values that are to be changed, aka are Some()
-        update_db_row(&job_handler_task).unwrap();
+        update_db_row(&job_handler_task).await.unwrap();
     } else {
         log::error!("Tried to update non-existent job");
-        bot.send_message(ChatId(job_handler_task.chat_id.unwrap()), NO_DB_MSG)
+        let chat_id = ChatId(job_handler_task.chat_id.unwrap());
+        if let Err(e) = bot
+            .send_message(chat_id, chat_lang(chat_id, None).texts().no_db)
             .await
-            .unwrap();
+        {
+            handle_send_error(chat_id.0, e, sched).await;
+        }
     }
 }
 
@@ -140,34 +191,142 @@ pub async fn handle_delete_registration_task(
 ) {
     log::info!("Unregister: {}", &job_handler_task.chat_id.unwrap());
 
-    // unregister is only invoked if existence of job is guaranteed
-    let registration = get_user_registration(job_handler_task.chat_id.unwrap()).unwrap();
+    // the chat may have been removed while this task was queued
+    let Some(registration) = get_user_registration(job_handler_task.chat_id.unwrap()) else {
+        log::warn!("Tried to unregister non-existent chat");
+        return;
+    };
 
-    // unload old job
-    sched
-        .context
-        .job_delete_tx
-        .send(registration.job_uuid.unwrap())
-        .unwrap();
+    // unload old jobs
+    for uuid in &registration.job_uuids {
+        sched.context.job_delete_tx.send(*uuid).unwrap();
+    }
 
-    // kill uuid from this thing
+    // kill uuids from this thing
     insert_user_registration(
         job_handler_task.chat_id.unwrap(),
         RegistrationEntry {
-            job_uuid: None,
+            job_uuids: vec![],
             mensa_id: registration.mensa_id,
+            additional_mensa_ids: registration.additional_mensa_ids,
             hour: None,
             minute: None,
-            allergens: registration.allergens,
-            senddiff: registration.senddiff,
+            settings: registration.settings,
+            schedule: registration.schedule,
         },
     );
 
     // delete from db
-    task_db_kill_auto(job_handler_task.chat_id.unwrap()).unwrap();
+    task_db_kill_auto(job_handler_task.chat_id.unwrap())
+        .await
+        .unwrap();
 }
 
-pub async fn handle_broadcast_update_task(bot: &Bot, job_handler_task: JobHandlerTask) {
+pub async fn handle_update_schedule_task(
+    bot: &Bot,
+    job_handler_task: JobHandlerTask,
+    sched: &JobScheduler,
+) {
+    let chat_id = job_handler_task.chat_id.unwrap();
+    // schedule was already saved by the handler
+    if let Some(mut registration) = get_user_registration(chat_id) {
+        log::info!("{} changed schedule: {:?}", chat_id, registration.schedule);
+
+        for uuid in &registration.job_uuids {
+            sched.context.job_delete_tx.send(*uuid).unwrap();
+        }
+
+        let task = UpdateRegistrationTask {
+            chat_id,
+            mensa_id: Some(registration.mensa_id),
+            hour: registration.hour,
+            minute: registration.minute,
+        };
+        registration.job_uuids =
+            load_job(bot.clone(), sched, task.into(), &registration.schedule).await;
+
+        insert_user_registration(chat_id, registration);
+    } else {
+        log::error!("Tried to update schedule of non-existent job");
+    }
+}
+
+pub async fn handle_remove_chat_task(job_handler_task: JobHandlerTask, sched: &JobScheduler) {
+    remove_chat(job_handler_task.chat_id.unwrap(), sched).await;
+}
+
+/// Unloads the jobs of a chat and deletes all of its data, it has to /start again
+pub async fn remove_chat(chat_id: i64, sched: &JobScheduler) {
+    log::info!("Removing chat {}", chat_id);
+
+    let registration = USER_REGISTRATIONS
+        .get()
+        .unwrap()
+        .write()
+        .unwrap()
+        .remove(&chat_id);
+    if let Some(registration) = registration {
+        for uuid in &registration.job_uuids {
+            sched.context.job_delete_tx.send(*uuid).unwrap();
+        }
+    }
+
+    if let Err(e) = delete_chat(chat_id).await {
+        log::error!("Deleting chat {} failed: {}", chat_id, e);
+    }
+}
+
+/// Blocked by the user, deleted account or the bot was removed from the group
+pub fn chat_is_gone(err: &RequestError) -> bool {
+    matches!(
+        err,
+        RequestError::Api(
+            ApiError::BotBlocked
+                | ApiError::BotKicked
+                | ApiError::BotKickedFromSupergroup
+                | ApiError::ChatNotFound
+                | ApiError::UserDeactivated
+                | ApiError::GroupDeactivated
+        )
+    ) || matches!(
+        err,
+        // teloxide has no variants for these (basic groups and channels)
+        RequestError::Api(ApiError::Unknown(description))
+            if GONE_CHAT_ERRORS.iter().any(|gone| description.contains(gone))
+    )
+}
+
+/// Chats that are gone are removed, any other error is only logged
+pub async fn handle_send_error(chat_id: i64, err: RequestError, sched: &JobScheduler) {
+    if chat_is_gone(&err) {
+        log::warn!("Chat {} is gone: {}", chat_id, err);
+        remove_chat(chat_id, sched).await;
+    } else {
+        log::error!("Sending to {} failed: {}", chat_id, err);
+    }
+}
+
+/// `handle_send_error` for dispatcher handlers, the chat is removed by the job handler
+pub fn handle_send_error_task(
+    chat_id: i64,
+    err: RequestError,
+    jobhandler_task_tx: &Sender<JobHandlerTask>,
+) -> HandlerResult {
+    if chat_is_gone(&err) {
+        log::warn!("Chat {} is gone: {}", chat_id, err);
+        jobhandler_task_tx.send(RemoveChatTask { chat_id }.into())?;
+    } else {
+        log::error!("Sending to {} failed: {}", chat_id, err);
+    }
+
+    Ok(())
+}
+
+pub async fn handle_broadcast_update_task(
+    bot: &Bot,
+    job_handler_task: JobHandlerTask,
+    sched: &JobScheduler,
+) {
     log::info!(
         "TodayMeals changed @Mensa {}",
         &job_handler_task.meals_diff.as_ref().unwrap().canteen_id
@@ -175,21 +334,41 @@ pub async fn handle_broadcast_update_task(bot: &Bot, job_handler_task: JobHandle
 
     let diff = job_handler_task.meals_diff.unwrap();
 
+    // cached plan of today is outdated now
+    if let Err(e) = invalidate_cached_meals(
+        BACKEND.get().unwrap().name(),
+        diff.canteen_id,
+        chrono::Local::now().date_naive(),
+    )
+    .await
+    {
+        log::error!("Invalidating meal cache failed: {}", e);
+    }
+
     let workaround = USER_REGISTRATIONS.get().unwrap().read().unwrap().clone();
     for (chat_id, registration_data) in workaround {
-        let canteen_id = registration_data.mensa_id;
+        let mensa_ids = registration_data.mensa_ids();
 
         let now = chrono::Local::now();
 
-        if let (Some(job_hour), Some(job_minute)) =
-            (registration_data.hour, registration_data.minute)
+        // today's send time, weekday overrides included; nothing for disabled days
+        if let Some((job_hour, job_minute)) = registration_data
+            .hour
+            .zip(registration_data.minute)
+            .and_then(|(hour, minute)| {
+                registration_data.schedule.send_time(
+                    now.weekday().num_days_from_monday(),
+                    hour,
+                    minute,
+                )
+            })
         {
-            // send update to all subscribers of this mensa id
-            if canteen_id == diff.canteen_id
+            // send update to all chats following this mensa id
+            if mensa_ids.contains(&diff.canteen_id)
                         // only send updates after job message has been sent: job hour has to be earlier OR same hour, earlier minute
                         && (job_hour < now.hour() || job_hour == now.hour() && job_minute <= now.minute())
             {
-                if env::var_os("ALLERGEN_DBG").is_some() && canteen_id == 118 {
+                if env::var_os("ALLERGEN_DBG").is_some() && diff.canteen_id == 118 {
                     let len_all = diff.modified_meals.as_ref().map(|t| t.len()).unwrap_or(0);
                     let len_non_alg = diff
                         .modified_meals_ignoring_allergens
@@ -206,7 +385,7 @@ pub async fn handle_broadcast_update_task(bot: &Bot, job_handler_task: JobHandle
                 }
 
                 // user doesnt display allergens, but only only diff is allergens -> skip
-                if !registration_data.allergens
+                if !registration_data.settings.allergens
                     && diff.new_meals.is_none()
                     && diff.removed_meals.is_none()
                     && diff.modified_meals_ignoring_allergens.is_none()
@@ -214,19 +393,30 @@ pub async fn handle_broadcast_update_task(bot: &Bot, job_handler_task: JobHandle
                     continue;
                 }
 
-                log::info!("Sent update to {}", chat_id);
-
-                let text = match registration_data.senddiff {
-                    true => stuwe_build_diff_msg(&diff, registration_data.allergens).await,
+                let options = MealRenderOptions::from(&registration_data);
+                let text = match registration_data.settings.senddiff {
+                    true => {
+                        // name the changed mensa if the plan shows several
+                        let mensa_name = (mensa_ids.len() > 1).then(|| mensa_name(diff.canteen_id));
+                        match stuwe_build_diff_msg(&diff, mensa_name.as_deref(), &options).await {
+                            Some(text) => text,
+                            // only meals outside of the user's diet changed
+                            None => continue,
+                        }
+                    }
                     false => {
-                        stuwe_build_meal_msg(0, diff.canteen_id, registration_data.allergens).await
+                        build_meal_msg(resolve_days_forward(0), &[diff.canteen_id], &options).await
                     }
                 };
 
-                bot.send_message(ChatId(chat_id), text)
+                match bot
+                    .send_message(ChatId(chat_id), text)
                     .parse_mode(ParseMode::MarkdownV2)
                     .await
-                    .unwrap();
+                {
+                    Ok(_) => log::info!("Sent update to {}", chat_id),
+                    Err(e) => handle_send_error(chat_id, e, sched).await,
+                }
             }
         }
     }
@@ -238,13 +428,14 @@ pub async fn start_mensaupd_hook_and_campusdual_job(
     job_handler_tx: Sender<JobHandlerTask>,
 ) {
     // listen for mensa updates
-    if *BACKEND.get().unwrap() == Backend::StuWe {
+    let backend = BACKEND.get().unwrap();
+    if backend.capabilities().diffs {
         tokio::spawn(async move {
             loop {
                 let tx = job_handler_tx.clone();
-                let h = await_handle_mealplan_upd(tx).await;
+                let h = backend.listen_for_updates(tx).await;
                 if h.is_err() {
-                    log::error!("WebSocket connection failed");
+                    log::error!("Mensa update connection failed");
                 }
                 sleep(Duration::from_secs(5)).await;
             }
@@ -262,40 +453,31 @@ pub async fn start_mensaupd_hook_and_campusdual_job(
     sched.add(cache_and_broadcast_job).await.unwrap();
 }
 
-async fn await_handle_mealplan_upd(job_handler_tx: Sender<JobHandlerTask>) -> Result<()> {
-    let response = Client::default()
-        .get(format!("{}/today_updated_diff_ws", API_URL.get().unwrap()))
-        .upgrade() // Prepares the WebSocket upgrade.
-        .send()
-        .await?;
-
-    // Turns the response into a WebSocket stream.
-    let mut websocket = response.into_websocket().await?;
-    log::info!("MensaUpdate WebSocket connected");
-
-    while let Some(message) = websocket.try_next().await? {
-        if let Message::Text(text) = message {
-            job_handler_tx.send(
-                BroadcastUpdateTask {
-                    meals_diff: serde_json::from_str(&text)?,
-                }
-                .into(),
-            )?;
-        }
-    }
+pub async fn start_meal_watch_job(bot: Bot, sched: &JobScheduler) {
+    let sched_handle = sched.clone();
+    // after the mensa plans are usually updated, and during the morning
+    let meal_watch_job = Job::new_async("0 30 7,11,17 * * Mon-Fri", move |_uuid, mut _l| {
+        let bot = bot.clone();
+        let sched = sched_handle.clone();
 
-    Ok(())
+        Box::pin(async move {
+            scan_meal_watches(&bot, &sched).await;
+        })
+    })
+    .unwrap();
+    sched.add(meal_watch_job).await.unwrap();
 }
 
 async fn check_notify_campusdual_grades_signups(bot: Bot) {
     if let Some(cd_data) = CD_DATA.get() {
         log::info!("Updating CampusDual");
+        let texts = chat_lang(ChatId(cd_data.chat_id), None).texts();
         match get_campusdual_data(cd_data.username.clone(), cd_data.password.clone()).await {
             Ok((grades, signup_options)) => {
                 if let Some(new_grades) = compare_campusdual_grades(&grades).await {
                     log::info!("Got new grades! Sending to {}", cd_data.chat_id);
 
-                    let mut msg = String::from("Neue Note:");
+                    let mut msg = String::from(texts.new_grade);
                     for grade in new_grades {
                         msg.push_str(&format!("\n{}: {}", grade.name, grade.grade));
                     }
@@ -311,7 +493,7 @@ async fn check_notify_campusdual_grades_signups(bot: Bot) {
                 {
                     log::info!("Got new signup options! Sending to {}", cd_data.chat_id);
 
-                    let mut msg = String::from("Neue Anmeldemöglichkeit:");
+                    let mut msg = String::from(texts.new_signup_option);
                     for signup_option in new_signup_options {
                         msg.push_str(&format!(
                             "\n{} ({}) — {}",
@@ -337,26 +519,50 @@ pub async fn load_jobs_from_db(
     bot: &Bot,
     sched: &JobScheduler,
 ) -> BTreeMap<i64, RegistrationEntry> {
-    let mut loaded_user_data: BTreeMap<i64, RegistrationEntry> = BTreeMap::new();
-    let tasks_from_db = get_all_user_registrations_db().unwrap();
+    let mut loaded_user_data = get_all_user_registrations_db().await.unwrap();
+
+    for (chat_id, registration) in loaded_user_data.iter_mut() {
+        let task = UpdateRegistrationTask {
+            chat_id: *chat_id,
+            mensa_id: Some(registration.mensa_id),
+            hour: registration.hour,
+            minute: registration.minute,
+        }
+        .into();
+        registration.job_uuids = load_job(bot.clone(), sched, task, &registration.schedule).await;
+    }
 
-    for task in tasks_from_db {
-        let bot = bot.clone();
+    loaded_user_data
+}
 
-        let uuid = load_job(bot, sched, task.clone()).await;
-        loaded_user_data.insert(
-            task.chat_id.unwrap(),
-            RegistrationEntry {
-                job_uuid: uuid,
-                mensa_id: task.mensa_id.unwrap(),
-                hour: task.hour,
-                minute: task.minute,
-                // hack job
-                allergens: get_user_allergen_state(task.chat_id.unwrap()).unwrap(),
-                senddiff: get_user_senddiff_state(task.chat_id.unwrap()).unwrap(),
-            },
-        );
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn api_error(error: ApiError) -> RequestError {
+        RequestError::Api(error)
     }
 
-    loaded_user_data
+    #[test]
+    fn gone_chats() {
+        assert!(chat_is_gone(&api_error(ApiError::BotBlocked)));
+        assert!(chat_is_gone(&api_error(ApiError::ChatNotFound)));
+        assert!(chat_is_gone(&api_error(ApiError::Unknown(
+            "Forbidden: bot was kicked from the group chat".to_string()
+        ))));
+        assert!(chat_is_gone(&api_error(ApiError::Unknown(
+            "Forbidden: bot is not a member of the group chat".to_string()
+        ))));
+    }
+
+    #[test]
+    fn other_errors_keep_the_chat() {
+        assert!(!chat_is_gone(&api_error(ApiError::MessageIsTooLong)));
+        assert!(!chat_is_gone(&api_error(ApiError::Unknown(
+            "Bad Request: can't parse entities".to_string()
+        ))));
+        assert!(!chat_is_gone(&RequestError::RetryAfter(
+            teloxide::types::Seconds::from_seconds(3)
+        )));
+    }
 }